@@ -0,0 +1,127 @@
+use std::env;
+
+use bip39::Mnemonic;
+use coins_bip32::prelude::{SigningKey, XPriv};
+
+use crate::address::address_of;
+use crate::profile::Profile;
+use crate::secrets::parse_private_key;
+
+/// The mnemonic anvil derives its default accounts from
+pub const ANVIL_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+/// Number of accounts anvil funds by default
+pub const ANVIL_ACCOUNTS: u32 = 10;
+
+/// Environment variable holding a custom mnemonic for `mnemonic:<index>` wallets
+const MNEMONIC_VAR: &str = "BERRY_MNEMONIC";
+
+/// An account derived from a mnemonic
+pub struct Account {
+    pub index: u32,
+    pub address: String,
+    pub private_key: String,
+}
+
+/// Derive the account at `m/44'/60'/0'/0/<index>`, the path anvil and most wallets use
+pub fn derive(mnemonic: &str, index: u32) -> Result<Account, String> {
+    let mnemonic = Mnemonic::parse(mnemonic).map_err(|e| format!("Invalid mnemonic: {}", e))?;
+    let root = XPriv::root_from_seed(&mnemonic.to_seed(""), None)
+        .map_err(|e| format!("Failed to derive root key: {}", e))?;
+    let child = root
+        .derive_path(format!("m/44'/60'/0'/0/{}", index).as_str())
+        .map_err(|e| format!("Failed to derive account {}: {}", index, e))?;
+
+    let signing_key: &SigningKey = child.as_ref();
+    let private_key = format!("0x{}", hex::encode(signing_key.to_bytes()));
+    Ok(Account {
+        index,
+        address: address_of(&private_key)?,
+        private_key,
+    })
+}
+
+/// The mnemonic a profile derives its accounts from
+///
+/// A custom mnemonic comes from BERRY_MNEMONIC; anvil's public test mnemonic is
+/// only used for local profiles.
+pub fn profile_mnemonic(profile: &Profile) -> Result<String, String> {
+    if let Ok(mnemonic) = env::var(MNEMONIC_VAR) {
+        return Ok(mnemonic);
+    }
+    if profile.is_local() {
+        return Ok(ANVIL_MNEMONIC.to_string());
+    }
+    Err(format!(
+        "Profile '{}' derives accounts from a mnemonic but {} is not set; anvil's test mnemonic is only used for local profiles",
+        profile.name, MNEMONIC_VAR
+    ))
+}
+
+/// Private keys of anvil's default accounts, derived from [`ANVIL_MNEMONIC`]
+const ANVIL_KEYS: [&str; ANVIL_ACCOUNTS as usize] = [
+    "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+    "5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a",
+    "7c852118294e51e653712a81e05800f419141751be58f605c371e15141b007a6",
+    "47e179ec197488593b187f80a00eb0da91f1b9d0b13f8733639f19c30a34926a",
+    "8b3a350cf5c34c9194ca85829a2df0ec3153be0318b5e2d3348e872092edffba",
+    "92db14e403b83dfe3df233f83dfa3a0d7096f21ca9b0d6d6b8d88b2b4ec1564e",
+    "4bbbf85ce3377467afe5d46f804f221813b2bb87f24d81f60f1fcdbf7cbf4356",
+    "dbda1821b80551c9d65939329250298aa3472ba22feea921c0cf5d620ea67b97",
+    "2a871d0798f97d79848a013d4936a73bf4cc922c825d33c1cf7073dff6d409c6",
+];
+
+/// Whether a private key belongs to one of anvil's default accounts
+pub fn is_anvil_key(private_key: &str) -> bool {
+    parse_private_key(private_key).is_ok_and(|key| ANVIL_KEYS.contains(&hex::encode(key).as_str()))
+}
+
+/// `berry accounts list`: show the addresses derived from a mnemonic
+pub fn list_accounts(mnemonic: Option<&str>, count: u32) -> Result<(), String> {
+    let mnemonic = mnemonic.unwrap_or(ANVIL_MNEMONIC);
+    for index in 0..count {
+        let account = derive(mnemonic, index)?;
+        println!("({}) {}", account.index, account.address);
+    }
+    Ok(())
+}
+
+/// `berry accounts derive`: show the address and private key at one index
+pub fn derive_account(mnemonic: Option<&str>, index: u32) -> Result<(), String> {
+    let account = derive(mnemonic.unwrap_or(ANVIL_MNEMONIC), index)?;
+    println!("Index:       {}", account.index);
+    println!("Address:     {}", account.address);
+    println!("Private key: {}", account.private_key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anvil_keys_match_the_mnemonic() {
+        for (index, key) in ANVIL_KEYS.iter().enumerate() {
+            let account = derive(ANVIL_MNEMONIC, index as u32).unwrap();
+            assert_eq!(account.private_key, format!("0x{}", key));
+        }
+    }
+
+    #[test]
+    fn recognises_anvil_keys_in_any_form() {
+        assert!(is_anvil_key(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        ));
+        assert!(is_anvil_key(
+            "59C6995E998F97A5A0044966F0945389DC9E86DAE88C7A8412F4603B6B78690D"
+        ));
+        assert!(is_anvil_key(
+            " 0x2a871d0798f97d79848a013d4936a73bf4cc922c825d33c1cf7073dff6d409c6\n"
+        ));
+        assert!(!is_anvil_key(
+            "0x0000000000000000000000000000000000000000000000000000000000000001"
+        ));
+        assert!(!is_anvil_key("not a key"));
+    }
+}
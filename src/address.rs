@@ -0,0 +1,116 @@
+use k256::ecdsa::SigningKey;
+use sha3::{Digest, Keccak256};
+
+use crate::secrets::parse_private_key;
+
+/// Derive the EIP-55 checksummed address of a hex private key
+pub fn address_of(private_key: &str) -> Result<String, String> {
+    let key = parse_private_key(private_key)?;
+    let signing_key =
+        SigningKey::from_slice(&key).map_err(|_| "Private key is not a valid secp256k1 key")?;
+    let point = signing_key.verifying_key().to_encoded_point(false);
+    // Skip the 0x04 prefix of the uncompressed public key
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    Ok(checksum(&hex::encode(&hash[12..])))
+}
+
+/// Parse an address, rejecting mixed-case input whose EIP-55 checksum is wrong
+///
+/// Returns the checksummed form.
+pub fn parse_address(address: &str) -> Result<String, String> {
+    let hex_part = address
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Address '{}' must start with 0x", address))?;
+    if hex_part.len() != 40 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Address '{}' must be 0x followed by 40 hex digits",
+            address
+        ));
+    }
+
+    let checksummed = checksum(hex_part);
+    let all_lower = hex_part == hex_part.to_lowercase();
+    let all_upper = hex_part == hex_part.to_uppercase();
+    if !all_lower && !all_upper && checksummed != address {
+        return Err(format!(
+            "Address '{}' has an invalid EIP-55 checksum, expected {}",
+            address, checksummed
+        ));
+    }
+    Ok(checksummed)
+}
+
+/// Check that an address belongs to a private key
+pub fn check_pair(address: &str, private_key: &str) -> Result<String, String> {
+    let derived = address_of(private_key)?;
+    if parse_address(address)? != derived {
+        return Err(format!(
+            "Wallet address {} does not match the private key, which belongs to {}",
+            address, derived
+        ));
+    }
+    Ok(derived)
+}
+
+/// Apply the EIP-55 mixed-case checksum to 40 hex digits
+fn checksum(hex_part: &str) -> String {
+    let lower = hex_part.to_lowercase();
+    let hash = hex::encode(Keccak256::digest(lower.as_bytes()));
+    let mut address = String::from("0x");
+    for (c, h) in lower.chars().zip(hash.chars()) {
+        if c.is_ascii_alphabetic() && h >= '8' {
+            address.push(c.to_ascii_uppercase());
+        } else {
+            address.push(c);
+        }
+    }
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from EIP-55
+    const CHECKSUMMED: &[&str] = &[
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+        "0xde709f2102306220921060314715629080e2fb77",
+        "0x27b1fdb04752bbc536007a920d24acb045561c26",
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn checksums_eip55_vectors() {
+        for address in CHECKSUMMED {
+            assert_eq!(checksum(&address[2..]), *address);
+            assert_eq!(checksum(&address[2..].to_uppercase()), *address);
+        }
+    }
+
+    #[test]
+    fn parse_address_accepts_single_case_and_valid_checksums() {
+        let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert_eq!(parse_address(address).unwrap(), address);
+        assert_eq!(parse_address(&address.to_lowercase()).unwrap(), address);
+        assert!(parse_address("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+        assert!(parse_address("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+        assert!(parse_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").is_err());
+    }
+
+    #[test]
+    fn derives_anvil_account_addresses() {
+        assert_eq!(
+            address_of("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
+                .unwrap(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(
+            address_of("59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d").unwrap(),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::CHECK_MARK;
+use crate::environment::add_to_gitignore;
+use crate::r0vm;
+
+/// Port `berry bonsai-mock` listens on, matching the built-in ci profile
+pub const BONSAI_MOCK_PORT: u16 = 8081;
+
+/// Uploaded images, inputs and receipts, relative to the project root
+const DATA_DIR: &str = ".berry/bonsai-mock";
+
+/// Body of POST /sessions/create
+#[derive(Deserialize)]
+struct ProofRequest {
+    img: String,
+    input: String,
+    #[serde(default)]
+    assumptions: Vec<String>,
+    #[serde(default)]
+    execute_only: bool,
+}
+
+/// A proving session, executed in dev mode
+struct Session {
+    status: &'static str,
+    error: Option<String>,
+    stats: Option<r0vm::ExecStats>,
+    logs: String,
+    started: Instant,
+    elapsed: Option<f64>,
+}
+
+/// Shared server state
+struct Mock {
+    base_url: String,
+    data: PathBuf,
+    sessions: Mutex<HashMap<String, Session>>,
+    /// SNARK request ID to the session it compresses
+    snarks: Mutex<HashMap<String, String>>,
+}
+
+/// `berry bonsai-mock`: serve the Bonsai REST API locally with dev-mode receipts
+pub fn run_bonsai_mock(dir: Option<&str>, port: u16) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let data = dir.join(DATA_DIR);
+    for kind in ["images", "inputs", "receipts"] {
+        fs::create_dir_all(data.join(kind))
+            .map_err(|e| format!("Failed to create {}/{}: {}", DATA_DIR, kind, e))?;
+    }
+    add_to_gitignore(dir, "berry runtime state and logs", &[".berry/"])?;
+
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+    let mock = Arc::new(Mock {
+        base_url: format!("http://127.0.0.1:{}", port),
+        data,
+        sessions: Mutex::new(HashMap::new()),
+        snarks: Mutex::new(HashMap::new()),
+    });
+
+    println!(
+        "{} Bonsai mock listening on {} (receipts are dev-mode fakes)",
+        CHECK_MARK, mock.base_url
+    );
+    println!(
+        "Point the host at it with BONSAI_API_URL={} RISC0_PROVER=bonsai RISC0_DEV_MODE=1",
+        mock.base_url
+    );
+    println!("Press Ctrl-C to stop");
+
+    for request in server.incoming_requests() {
+        let mock = Arc::clone(&mock);
+        thread::spawn(move || handle(&mock, request));
+    }
+    Ok(())
+}
+
+fn handle(mock: &Arc<Mock>, mut request: Request) {
+    let method = request.method().clone();
+    // Report whichever risc0-zkvm version the client speaks
+    let version = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("x-risc0-version"))
+        .map(|h| h.value.to_string());
+    let url = request.url().to_string();
+    let segments: Vec<&str> = url
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_matches('/')
+        .split('/')
+        .collect();
+
+    let mut body = Vec::new();
+    let result = request
+        .as_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| (400, format!("Failed to read request body: {}", e)))
+        .and_then(|_| route(mock, &method, &segments, body, version));
+
+    let response = match result {
+        Ok(Reply::Json(value)) => Response::from_data(value.to_string())
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap()),
+        Ok(Reply::Bytes(bytes)) => Response::from_data(bytes),
+        Ok(Reply::Empty(status)) => Response::from_data(Vec::new()).with_status_code(status),
+        Err((status, message)) => Response::from_data(message).with_status_code(status),
+    };
+    println!("{} {} -> {}", method, url, response.status_code().0);
+    let _ = request.respond(response);
+}
+
+enum Reply {
+    Json(Value),
+    Bytes(Vec<u8>),
+    Empty(u16),
+}
+
+type RouteResult = Result<Reply, (u16, String)>;
+
+fn route(
+    mock: &Arc<Mock>,
+    method: &Method,
+    segments: &[&str],
+    body: Vec<u8>,
+    version: Option<String>,
+) -> RouteResult {
+    match (method, segments) {
+        (Method::Get, ["version"]) => Ok(Reply::Json(
+            json!({ "risc0_zkvm": version.into_iter().collect::<Vec<_>>() }),
+        )),
+        (Method::Get, ["user", "quotas"]) => Ok(Reply::Json(json!({
+            "exec_cycle_limit": i64::MAX,
+            "concurrent_proofs": i64::MAX,
+            "cycle_budget": i64::MAX,
+            "cycle_usage": 0,
+            "dedicated_executor": 0,
+            "dedicated_gpu": 0,
+        }))),
+        (Method::Get, ["images", "upload", id]) => {
+            if mock.file("images", id)?.exists() {
+                return Ok(Reply::Empty(204));
+            }
+            Ok(Reply::Json(
+                json!({ "url": mock.url(&["upload", "images", id]) }),
+            ))
+        }
+        (Method::Get, [kind @ ("inputs" | "receipts"), "upload"]) => {
+            let uuid = new_uuid();
+            Ok(Reply::Json(json!({
+                "url": mock.url(&["upload", kind, &uuid]),
+                "uuid": uuid,
+            })))
+        }
+        (Method::Put, ["upload", kind @ ("images" | "inputs" | "receipts"), id]) => {
+            fs::write(mock.file(kind, id)?, body)
+                .map_err(|e| (500, format!("Failed to store {} {}: {}", kind, id, e)))?;
+            Ok(Reply::Empty(200))
+        }
+        (Method::Delete, [kind @ ("images" | "inputs"), id]) => {
+            fs::remove_file(mock.file(kind, id)?)
+                .map_err(|_| (404, format!("No {} {}", kind, id)))?;
+            Ok(Reply::Empty(204))
+        }
+        (Method::Post, ["sessions", "create"]) => {
+            let request: ProofRequest = serde_json::from_slice(&body)
+                .map_err(|e| (400, format!("Invalid proof request: {}", e)))?;
+            create_session(mock, request)
+        }
+        (Method::Get, ["sessions", "status", id]) => session_status(mock, id),
+        (Method::Get, ["sessions", "logs", id]) => {
+            let sessions = mock.sessions.lock().unwrap();
+            let session = sessions
+                .get(*id)
+                .ok_or_else(|| (404, format!("No session {}", id)))?;
+            Ok(Reply::Bytes(session.logs.clone().into_bytes()))
+        }
+        (Method::Get, ["sessions", "stop", id]) => {
+            let mut sessions = mock.sessions.lock().unwrap();
+            let session = sessions
+                .get_mut(*id)
+                .ok_or_else(|| (404, format!("No session {}", id)))?;
+            if session.status == "RUNNING" {
+                session.status = "ABORTED";
+                session.error = Some("Stopped by request".to_string());
+            }
+            Ok(Reply::Empty(200))
+        }
+        (Method::Get, ["sessions", "exec_only_journal", _]) => Err((
+            501,
+            "berry bonsai-mock does not extract journals; download the receipt instead".to_string(),
+        )),
+        (Method::Get, ["receipts", id]) => {
+            if !mock.file("receipts", id)?.exists() {
+                return Err((404, format!("No receipt for session {}", id)));
+            }
+            Ok(Reply::Json(json!({ "url": mock.url(&["download", id]) })))
+        }
+        (Method::Get, ["download", id]) => fs::read(mock.file("receipts", id)?)
+            .map(Reply::Bytes)
+            .map_err(|_| (404, format!("No receipt {}", id))),
+        (Method::Post, ["snark", "create"]) => {
+            let request: Value = serde_json::from_slice(&body)
+                .map_err(|e| (400, format!("Invalid snark request: {}", e)))?;
+            let session = request["session_id"]
+                .as_str()
+                .ok_or_else(|| (400, "session_id is missing".to_string()))?;
+            if !mock.sessions.lock().unwrap().contains_key(session) {
+                return Err((404, format!("No session {}", session)));
+            }
+            let uuid = new_uuid();
+            mock.snarks
+                .lock()
+                .unwrap()
+                .insert(uuid.clone(), session.to_string());
+            Ok(Reply::Json(json!({ "uuid": uuid })))
+        }
+        (Method::Get, ["snark", "status", id]) => {
+            let session = mock
+                .snarks
+                .lock()
+                .unwrap()
+                .get(*id)
+                .cloned()
+                .ok_or_else(|| (404, format!("No snark request {}", id)))?;
+            // Fake receipts need no compression, the session receipt stands in for the SNARK
+            let Reply::Json(status) = session_status(mock, &session)? else {
+                unreachable!("session status is JSON")
+            };
+            Ok(Reply::Json(json!({
+                "status": status["status"],
+                "output": status["receipt_url"],
+                "error_msg": status["error_msg"],
+            })))
+        }
+        _ => Err((404, "Not found".to_string())),
+    }
+}
+
+fn create_session(mock: &Arc<Mock>, request: ProofRequest) -> RouteResult {
+    let elf = mock.file("images", &request.img)?;
+    let input = mock.file("inputs", &request.input)?;
+    if !elf.exists() {
+        return Err((404, format!("Image {} was not uploaded", request.img)));
+    }
+    if !input.exists() {
+        return Err((404, format!("Input {} was not uploaded", request.input)));
+    }
+    if !request.assumptions.is_empty() {
+        return Err((
+            501,
+            "berry bonsai-mock does not support assumptions".to_string(),
+        ));
+    }
+
+    let uuid = new_uuid();
+    mock.sessions.lock().unwrap().insert(
+        uuid.clone(),
+        Session {
+            status: "RUNNING",
+            error: None,
+            stats: None,
+            logs: String::new(),
+            started: Instant::now(),
+            elapsed: None,
+        },
+    );
+
+    let mock = Arc::clone(mock);
+    let id = uuid.clone();
+    thread::spawn(move || {
+        let receipt = mock.data.join("receipts").join(&id);
+        let receipt = (!request.execute_only).then_some(receipt.as_path());
+        let result = r0vm::execute(&elf, &input, receipt);
+
+        let mut sessions = mock.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(&id) else {
+            return;
+        };
+        if session.status != "RUNNING" {
+            return;
+        }
+        session.elapsed = Some(session.started.elapsed().as_secs_f64());
+        match result {
+            Ok(execution) => {
+                session.status = "SUCCEEDED";
+                session.stats = Some(execution.stats);
+                session.logs = execution.output;
+            }
+            Err(e) => {
+                session.status = "FAILED";
+                session.error = Some(e);
+            }
+        }
+    });
+    Ok(Reply::Json(json!({ "uuid": uuid })))
+}
+
+fn session_status(mock: &Mock, id: &str) -> RouteResult {
+    let sessions = mock.sessions.lock().unwrap();
+    let session = sessions
+        .get(id)
+        .ok_or_else(|| (404, format!("No session {}", id)))?;
+    let receipt_url = (session.status == "SUCCEEDED" && mock.file("receipts", id)?.exists())
+        .then(|| mock.url(&["download", id]));
+    // The SDK requires stats on success
+    let stats = session.stats.as_ref().map(|stats| {
+        json!({
+            "segments": stats.segments,
+            "total_cycles": stats.total_cycles,
+            "cycles": stats.user_cycles,
+        })
+    });
+    Ok(Reply::Json(json!({
+        "status": session.status,
+        "receipt_url": receipt_url,
+        "error_msg": session.error,
+        "state": (session.status == "RUNNING").then_some("Executor"),
+        "elapsed_time": session
+            .elapsed
+            .unwrap_or_else(|| session.started.elapsed().as_secs_f64()),
+        "stats": stats,
+    })))
+}
+
+impl Mock {
+    fn url(&self, segments: &[&str]) -> String {
+        format!("{}/{}", self.base_url, segments.join("/"))
+    }
+
+    /// Where an uploaded object is stored, rejecting IDs that would escape the data directory
+    fn file(&self, kind: &str, id: &str) -> Result<PathBuf, (u16, String)> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err((400, format!("Invalid ID '{}'", id)));
+        }
+        Ok(self.data.join(kind).join(id))
+    }
+}
+
+/// A random version 4 UUID
+fn new_uuid() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
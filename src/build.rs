@@ -0,0 +1,601 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use sha3::{Digest, Keccak256};
+use toml_edit::DocumentMut;
+
+use super::{new_spinner, CHECK_MARK, CROSS_MARK};
+use crate::environment::add_to_gitignore;
+use crate::image_id::report_drift;
+use crate::lock::installed_commit;
+use crate::walk::{project_files, project_files_matching};
+
+/// Where build logs go, relative to the project root
+const LOG_DIR: &str = ".berry/logs";
+
+/// Input fingerprints of the last successful builds, relative to the project root
+const BUILD_STATE: &str = ".berry/build.json";
+
+/// Files whose changes make the Rust targets stale
+const RUST_INPUTS: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "rust-toolchain.toml",
+    "build.rs",
+];
+
+/// Files besides `*.sol` whose changes make the contracts stale
+const CONTRACT_INPUTS: &[&str] = &[
+    "foundry.toml",
+    "remappings.txt",
+    "berry.lock",
+    "soldeer.lock",
+];
+
+/// Directories holding the Solidity libraries, which the project walk skips
+const LIBRARY_DIRS: &[&str] = &["lib", "dependencies"];
+
+/// How many compiler errors the summary lists before counting the rest
+const MAX_LISTED_ERRORS: usize = 10;
+
+/// Lines that only announce the errors already reported above them
+const ERROR_TRAILERS: &[&str] = &[
+    "error: could not compile",
+    "error: aborting due to",
+    "Error: Compiler run failed",
+];
+
+/// A compiler error and where it points, if it says
+struct CompilerError {
+    message: String,
+    location: Option<String>,
+}
+
+/// Run a build tool, streaming its output prefixed with `[label]` and logging it to
+/// `.berry/logs/<label>-build.log`
+///
+/// On failure the compiler errors found in the output are summarized.
+pub fn run_build_step(dir: &Path, label: &str, command: &mut Command) -> Result<(), String> {
+    run_streamed(dir, label, "build", command).map(|_| ())
+}
+
+/// Run `label`'s `task`, streaming its output prefixed with `[label]` and logging it to
+/// `.berry/logs/<label>-<task>.log`; returns the output lines
+///
+/// On failure the compiler errors found in the output are summarized.
+pub fn run_streamed(
+    dir: &Path,
+    label: &str,
+    task: &str,
+    command: &mut Command,
+) -> Result<Vec<String>, String> {
+    let log_name = format!("{}/{}-{}.log", LOG_DIR, label, task);
+    fs::create_dir_all(dir.join(LOG_DIR))
+        .map_err(|e| format!("Failed to create {}: {}", LOG_DIR, e))?;
+    let mut log = File::create(dir.join(&log_name))
+        .map_err(|e| format!("Failed to create {}: {}", log_name, e))?;
+    add_to_gitignore(dir, "berry runtime state and logs", &[".berry/"])?;
+
+    let pb = new_spinner(format!("Running {} {}...", label, task));
+    let started = Instant::now();
+    let mut child = command
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            pb.finish_with_message(format!("{} {} could not be started", CROSS_MARK, label));
+            format!("Failed to run {}: {}", label, e)
+        })?;
+
+    // Forward both pipes line by line so the output keeps its order as far as possible
+    let (sender, lines) = mpsc::channel();
+    let readers: Vec<_> = [
+        child
+            .stdout
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|pipe| {
+        let sender = sender.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                let _ = sender.send(line);
+            }
+        })
+    })
+    .collect();
+    drop(sender);
+
+    let mut output = Vec::new();
+    for line in lines {
+        let line = strip_ansi(&line);
+        let _ = writeln!(log, "{}", line);
+        // println would be dropped when the spinner is hidden, as it is in CI
+        pb.suspend(|| println!("[{}] {}", label, line));
+        output.push(line);
+    }
+    for reader in readers {
+        let _ = reader.join();
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for {}: {}", label, e))?;
+
+    let elapsed = started.elapsed().as_secs_f64();
+    if status.success() {
+        pb.finish_with_message(format!(
+            "{} {} {} ({:.1}s)",
+            CHECK_MARK, label, task, elapsed
+        ));
+        return Ok(output);
+    }
+
+    let errors = compiler_errors(&output);
+    pb.finish_with_message(format!(
+        "{} {} {} failed ({:.1}s)",
+        CROSS_MARK, label, task, elapsed
+    ));
+    if !errors.is_empty() {
+        println!("\n{} reported {} error(s):", label, errors.len());
+        for error in errors.iter().take(MAX_LISTED_ERRORS) {
+            match &error.location {
+                Some(location) => {
+                    println!("  {} {}\n      at {}", CROSS_MARK, error.message, location)
+                }
+                None => println!("  {} {}", CROSS_MARK, error.message),
+            }
+        }
+        if errors.len() > MAX_LISTED_ERRORS {
+            println!("  ... and {} more", errors.len() - MAX_LISTED_ERRORS);
+        }
+    }
+    Err(format!(
+        "{} {} exited with {}, full log in {}",
+        label, task, status, log_name
+    ))
+}
+
+/// Collect rustc (`error[E0425]: ...`) and solc (`Error (7576): ...`) errors with the
+/// `--> file:line` that follows them
+fn compiler_errors(output: &[String]) -> Vec<CompilerError> {
+    let mut errors: Vec<CompilerError> = Vec::new();
+    let mut awaiting_location = false;
+    for line in output {
+        let trimmed = line.trim();
+        if ERROR_TRAILERS.iter().any(|t| trimmed.starts_with(t)) {
+            awaiting_location = false;
+        } else if trimmed.starts_with("error[")
+            || trimmed.starts_with("error:")
+            || trimmed.starts_with("Error (")
+        {
+            errors.push(CompilerError {
+                message: trimmed.to_string(),
+                location: None,
+            });
+            awaiting_location = true;
+        } else if let Some(location) = trimmed.strip_prefix("-->") {
+            if awaiting_location {
+                if let Some(error) = errors.last_mut() {
+                    error.location = Some(location.trim().trim_end_matches(':').to_string());
+                }
+                awaiting_location = false;
+            }
+        }
+    }
+    errors
+}
+
+/// Remove terminal color codes so logs and error matching see plain text
+fn strip_ansi(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip the CSI sequence up to its final letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Parts of the project `berry build` builds; none selected means all
+pub struct BuildTargets {
+    pub guest: bool,
+    pub host: bool,
+    pub contracts: bool,
+    pub release: bool,
+}
+
+/// A guest method as embedded by risc0-build
+pub struct GuestMethod {
+    pub name: String,
+    pub image_id: String,
+    pub elf: PathBuf,
+}
+
+/// `berry build`: build the selected targets, skipping those whose inputs are unchanged
+pub fn run_build(dir: Option<&str>, targets: &BuildTargets) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let all = !(targets.guest || targets.host || targets.contracts);
+    let profile = if targets.release { "release" } else { "debug" };
+    let methods = methods_package(dir)?;
+    if targets.guest && methods.is_none() {
+        return Err(
+            "No guest methods crate found: no Cargo.toml has [package.metadata.risc0] methods"
+                .to_string(),
+        );
+    }
+
+    let mut state = load_state(dir);
+    let release: &[&str] = if targets.release { &["--release"] } else { &[] };
+    let target_dir = target_dir(dir);
+
+    if let Some((package, manifest_dir)) = methods.as_ref().filter(|_| all || targets.guest) {
+        let mut command = Command::new("cargo");
+        command.args(["build", "-p", package]).args(release);
+        let outputs = [target_dir.join(profile).join("build")];
+        let inputs = guest_inputs(dir, manifest_dir)?;
+        build_if_stale(
+            dir,
+            &format!("guest-{}", profile),
+            &inputs,
+            &outputs,
+            &mut state,
+            "guest",
+            &mut command,
+        )?;
+    }
+    if all || targets.host {
+        let mut command = Command::new("cargo");
+        command.args(["build", "--workspace"]).args(release);
+        let outputs = [target_dir.join(profile)];
+        let inputs = rust_inputs(dir)?;
+        build_if_stale(
+            dir,
+            &format!("host-{}", profile),
+            &inputs,
+            &outputs,
+            &mut state,
+            "host",
+            &mut command,
+        )?;
+    }
+    if all || targets.contracts {
+        let mut command = Command::new("forge");
+        command.arg("build");
+        let outputs = [dir.join("out")];
+        let inputs = contract_inputs(dir)?;
+        build_if_stale(
+            dir,
+            "contracts",
+            &inputs,
+            &outputs,
+            &mut state,
+            "forge",
+            &mut command,
+        )?;
+    }
+
+    if methods.is_some() && (all || targets.guest || targets.host) {
+        let guests = guest_methods(dir, targets.release)?;
+        println!("\nGuest methods ({}):", profile);
+        for guest in &guests {
+            let size = fs::metadata(&guest.elf)
+                .map(|m| format!("{:.1} KiB", m.len() as f64 / 1024.0))
+                .unwrap_or_else(|_| "ELF missing".to_string());
+            println!("  {}  image ID {}  {}", guest.name, guest.image_id, size);
+        }
+        println!();
+        report_drift(dir, &guests)?;
+    }
+    Ok(())
+}
+
+/// Run a build step unless its inputs match the last successful build and its outputs exist
+fn build_if_stale(
+    dir: &Path,
+    key: &str,
+    inputs: &[PathBuf],
+    outputs: &[PathBuf],
+    state: &mut BTreeMap<String, String>,
+    label: &str,
+    command: &mut Command,
+) -> Result<(), String> {
+    let fingerprint = fingerprint(dir, inputs);
+    if state.get(key) == Some(&fingerprint) && outputs.iter().all(|o| o.exists()) {
+        println!("{} {} is up to date", CHECK_MARK, label);
+        return Ok(());
+    }
+    run_build_step(dir, label, command)?;
+    state.insert(key.to_string(), fingerprint);
+    save_state(dir, state)
+}
+
+/// The guest methods of the last build, read from the methods.rs risc0-build generates
+pub fn guest_methods(dir: &Path, release: bool) -> Result<Vec<GuestMethod>, String> {
+    let (package, _) = methods_package(dir)?.ok_or_else(|| {
+        "No guest methods crate found: no Cargo.toml has [package.metadata.risc0] methods"
+            .to_string()
+    })?;
+    let profile = if release { "release" } else { "debug" };
+    let build_dir = target_dir(dir).join(profile).join("build");
+    let prefix = format!("{}-", package);
+
+    // Each build of the methods crate gets its own hashed directory; the newest is current
+    let methods_rs = fs::read_dir(&build_dir)
+        .map_err(|_| {
+            format!(
+                "No {} build found, run `berry build --guest` first",
+                profile
+            )
+        })?
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+        .map(|e| e.path().join("out/methods.rs"))
+        .filter(|p| p.is_file())
+        .max_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+        .ok_or_else(|| {
+            format!(
+                "No methods.rs for {} in {}, run `berry build --guest` first",
+                package,
+                build_dir.display()
+            )
+        })?;
+    let content = fs::read_to_string(&methods_rs)
+        .map_err(|e| format!("Failed to read {}: {}", methods_rs.display(), e))?;
+    parse_methods(&content).map_err(|e| format!("{} in {}", e, methods_rs.display()))
+}
+
+/// Pair the `<NAME>_PATH` and `<NAME>_ID` constants risc0-build writes to methods.rs
+fn parse_methods(content: &str) -> Result<Vec<GuestMethod>, String> {
+    let mut paths = BTreeMap::new();
+    let mut ids = BTreeMap::new();
+    for line in content.lines() {
+        let Some((name, value)) = line
+            .strip_prefix("pub const ")
+            .and_then(|rest| rest.split_once(':'))
+            .and_then(|(name, rest)| Some((name, rest.split_once('=')?.1)))
+        else {
+            continue;
+        };
+        let value = value.trim().trim_end_matches(';');
+        if let Some(method) = name.strip_suffix("_PATH") {
+            // Written with {:?}, which is valid JSON for the paths cargo produces
+            let path: String =
+                serde_json::from_str(value).map_err(|e| format!("Invalid {}: {}", name, e))?;
+            paths.insert(method.to_string(), PathBuf::from(path));
+        } else if let Some(method) = name.strip_suffix("_ID") {
+            let words: Vec<u32> =
+                serde_json::from_str(value).map_err(|e| format!("Invalid {}: {}", name, e))?;
+            let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+            ids.insert(method.to_string(), format!("0x{}", hex::encode(bytes)));
+        }
+    }
+
+    Ok(ids
+        .into_iter()
+        .filter_map(|(name, image_id)| {
+            let elf = paths.remove(&name)?;
+            Some(GuestMethod {
+                name,
+                image_id,
+                elf,
+            })
+        })
+        .collect())
+}
+
+/// The package that embeds the guests, and its directory
+fn methods_package(dir: &Path) -> Result<Option<(String, PathBuf)>, String> {
+    for manifest in project_files(dir, "Cargo.toml")? {
+        let content = fs::read_to_string(&manifest)
+            .map_err(|e| format!("Failed to read {}: {}", manifest.display(), e))?;
+        let Ok(doc) = content.parse::<DocumentMut>() else {
+            continue;
+        };
+        let package = doc.get("package");
+        let has_methods = package
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("risc0"))
+            .and_then(|r| r.get("methods"))
+            .is_some();
+        if let Some(name) = package.and_then(|p| p.get("name")).and_then(|n| n.as_str()) {
+            if has_methods {
+                let manifest_dir = manifest.parent().unwrap_or(dir).to_path_buf();
+                return Ok(Some((name.to_string(), manifest_dir)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn target_dir(dir: &Path) -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dir.join("target"))
+}
+
+fn is_rust_input(name: &str) -> bool {
+    name.ends_with(".rs") || RUST_INPUTS.contains(&name)
+}
+
+/// Sources of the methods crate and its guests, plus the workspace manifest and lock file
+fn guest_inputs(dir: &Path, methods_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut inputs = project_files_matching(methods_dir, is_rust_input)?;
+    for name in ["Cargo.toml", "Cargo.lock"] {
+        inputs.push(dir.join(name));
+    }
+    Ok(inputs)
+}
+
+fn rust_inputs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    project_files_matching(dir, is_rust_input)
+}
+
+fn contract_inputs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut inputs = project_files_matching(dir, |name| {
+        name.ends_with(".sol") || CONTRACT_INPUTS.contains(&name)
+    })?;
+
+    // Each installed library stands in for its sources through the commit it is at
+    for libs in LIBRARY_DIRS {
+        let Ok(entries) = fs::read_dir(dir.join(libs)) else {
+            continue;
+        };
+        let mut libraries: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        libraries.sort();
+        inputs.extend(libraries);
+    }
+    Ok(inputs)
+}
+
+/// Hash the paths and contents of the inputs; missing files hash as absent and
+/// directories as the library commit installed in them
+fn fingerprint(dir: &Path, inputs: &[PathBuf]) -> String {
+    let mut hasher = Keccak256::new();
+    for input in inputs {
+        let relative = input.strip_prefix(dir).unwrap_or(input);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        if input.is_dir() {
+            if let Some(commit) = installed_commit(input) {
+                hasher.update(commit.as_bytes());
+            }
+        } else if let Ok(content) = fs::read(input) {
+            hasher.update((content.len() as u64).to_le_bytes());
+            hasher.update(&content);
+        }
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn load_state(dir: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(dir.join(BUILD_STATE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(dir: &Path, state: &BTreeMap<String, String>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize build state: {}", e))?;
+    fs::write(dir.join(BUILD_STATE), json + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", BUILD_STATE, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::{GitSource, VcsInfo, VCS_INFO_FILE};
+
+    #[test]
+    fn parses_methods_rs() {
+        let content = r#"pub const BALANCE_OF_ELF: &[u8] = include_bytes!("/work/target/riscv-guest/methods/balance-of/riscv32im-risc0-zkvm-elf/release/balance-of.bin");
+pub const BALANCE_OF_PATH: &str = "/work/target/riscv-guest/methods/balance-of/riscv32im-risc0-zkvm-elf/release/balance-of.bin";
+pub const BALANCE_OF_ID: [u32; 8] = [67305985, 0, 0, 0, 0, 0, 0, 4294967295];
+"#;
+        let methods = parse_methods(content).unwrap();
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].name, "BALANCE_OF");
+        assert_eq!(
+            methods[0].image_id,
+            format!("0x01020304{}ffffffff", "0".repeat(48))
+        );
+        assert_eq!(
+            methods[0].elf,
+            PathBuf::from("/work/target/riscv-guest/methods/balance-of/riscv32im-risc0-zkvm-elf/release/balance-of.bin")
+        );
+    }
+
+    #[test]
+    fn skips_methods_without_a_path() {
+        let methods =
+            parse_methods("pub const IS_EVEN_ID: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];\n").unwrap();
+        assert!(methods.is_empty());
+        assert!(parse_methods(
+            "pub const IS_EVEN_ID: [u32; 8] = [1, 2];\npub const IS_EVEN_PATH: &str = not-json;\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn finds_rust_and_solidity_errors() {
+        let output: Vec<String> = [
+            "   Compiling guest v0.1.0",
+            "error[E0425]: cannot find value `x` in this scope",
+            " --> src/main.rs:2:5",
+            "  |",
+            "error: could not compile `guest` (bin \"guest\") due to 1 previous error",
+            "Error (7576): Undeclared identifier.",
+            "  --> src/Counter.sol:10:9:",
+            "Error: Compiler run failed:",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let errors = compiler_errors(&output);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].message,
+            "error[E0425]: cannot find value `x` in this scope"
+        );
+        assert_eq!(errors[0].location.as_deref(), Some("src/main.rs:2:5"));
+        assert_eq!(errors[1].message, "Error (7576): Undeclared identifier.");
+        assert_eq!(errors[1].location.as_deref(), Some("src/Counter.sol:10:9"));
+    }
+
+    #[test]
+    fn library_commits_change_the_contract_fingerprint() {
+        let dir = std::env::temp_dir().join(format!("berry-fingerprint-{}", std::process::id()));
+        let lib = dir.join("lib/forge-std");
+        fs::create_dir_all(&lib).unwrap();
+        fs::write(dir.join("Counter.sol"), "contract Counter {}").unwrap();
+        let vendor = |sha1: &str| {
+            let info = VcsInfo {
+                git: GitSource {
+                    url: "https://github.com/foundry-rs/forge-std".to_string(),
+                    rev: "v1.9.4".to_string(),
+                    sha1: sha1.to_string(),
+                },
+            };
+            fs::write(
+                lib.join(VCS_INFO_FILE),
+                serde_json::to_string(&info).unwrap(),
+            )
+            .unwrap();
+            let inputs = contract_inputs(&dir).unwrap();
+            assert!(inputs.contains(&lib));
+            fingerprint(&dir, &inputs)
+        };
+
+        let first = vendor("1714bee72e286e73f76e320d110e0eaf5c4e649d");
+        assert_eq!(vendor("1714bee72e286e73f76e320d110e0eaf5c4e649d"), first);
+        assert_ne!(vendor("3b20d60d14b343ee4f908cb8079495c07f5e8981"), first);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,444 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+use super::{CHECK_MARK, CROSS_MARK};
+use crate::libs::{is_commit, Revisions};
+use crate::lock::LOCK_FILE;
+use crate::remappings::{missing_targets, Remappings};
+use crate::walk::project_files;
+use crate::workspace::read_manifest;
+
+/// Dependency tables that may hold path or git dependencies
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// foundry.toml profile keys holding paths
+const FOUNDRY_PATH_KEYS: &[&str] = &[
+    "src",
+    "out",
+    "test",
+    "script",
+    "libs",
+    "cache_path",
+    "broadcast",
+    "remappings",
+    "allow_paths",
+    "fs_permissions",
+];
+
+/// A problem found in the generated project
+pub struct Problem {
+    pub file: PathBuf,
+    pub message: String,
+    pub fix: String,
+}
+
+/// Check a project and print every problem found
+pub fn run_check(dir: Option<&str>) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    if !dir.is_dir() {
+        return Err(format!("Directory '{}' not found", dir.display()));
+    }
+
+    let problems = check_project(dir)?;
+    if problems.is_empty() {
+        println!("{} No problems found", CHECK_MARK);
+        return Ok(());
+    }
+
+    print_problems(&problems);
+    Err(format!("Found {} problem(s)", problems.len()))
+}
+
+/// Print problems with their fix suggestions
+pub fn print_problems(problems: &[Problem]) {
+    for problem in problems {
+        println!(
+            "{} {}: {}",
+            CROSS_MARK,
+            problem.file.display(),
+            problem.message
+        );
+        println!("    fix: {}", problem.fix);
+    }
+}
+
+/// Run all consistency checks against a project directory
+pub fn check_project(dir: &Path) -> Result<Vec<Problem>, String> {
+    let root = dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", dir.display(), e))?;
+    let mut problems = Vec::new();
+
+    let manifests = project_files(&root, "Cargo.toml")?;
+    let mut risc0_refs = Vec::new();
+    for manifest in &manifests {
+        check_cargo_manifest(&root, manifest, &mut risc0_refs, &mut problems)?;
+    }
+
+    let foundry = root.join("foundry.toml");
+    if foundry.exists() {
+        check_foundry_config(&root, &foundry, &mut problems)?;
+    }
+
+    let remappings = root.join("remappings.txt");
+    if remappings.exists() {
+        check_remappings(&root, &remappings, &mut problems)?;
+    }
+
+    check_libraries(&root, &mut problems)?;
+    check_risc0_refs(&root, &risc0_refs, &mut problems);
+
+    for problem in &mut problems {
+        if let Ok(rel) = problem.file.strip_prefix(&root) {
+            problem.file = rel.to_path_buf();
+        }
+    }
+    Ok(problems)
+}
+
+/// Check path dependencies and collect risc0-ethereum git references
+fn check_cargo_manifest(
+    root: &Path,
+    manifest: &Path,
+    risc0_refs: &mut Vec<(PathBuf, String, String)>,
+    problems: &mut Vec<Problem>,
+) -> Result<(), String> {
+    let doc = read_manifest(manifest)?;
+    let base = manifest.parent().unwrap_or(root);
+
+    for (name, dep) in dependencies(&doc) {
+        if let Some(path) = dep.get("path").and_then(|p| p.as_str()) {
+            let resolved = normalize(&base.join(path));
+            if !resolved.starts_with(root) {
+                problems.push(Problem {
+                    file: manifest.to_path_buf(),
+                    message: format!(
+                        "dependency `{}` has path \"{}\", which is outside the project",
+                        name, path
+                    ),
+                    fix: format!(
+                        "replace it with a git dependency, e.g. {} = {{ {} }}",
+                        name,
+                        Revisions::pinned().cargo_source()
+                    ),
+                });
+            } else if !resolved.join("Cargo.toml").exists() {
+                problems.push(Problem {
+                    file: manifest.to_path_buf(),
+                    message: format!(
+                        "dependency `{}` points to \"{}\", which has no Cargo.toml",
+                        name, path
+                    ),
+                    fix: "check the path or restore the missing crate".to_string(),
+                });
+            }
+        }
+
+        let is_risc0 = dep.get("git").and_then(|g| g.as_str()).is_some_and(|git| {
+            git.trim_end_matches(".git")
+                .ends_with("risc0/risc0-ethereum")
+        });
+        if is_risc0 {
+            for key in ["branch", "tag", "rev"] {
+                if let Some(reference) = dep.get(key).and_then(|r| r.as_str()) {
+                    risc0_refs.push((manifest.to_path_buf(), name.clone(), reference.to_string()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every dependency entry in a manifest, including target-specific and workspace ones
+fn dependencies(doc: &DocumentMut) -> Vec<(String, &dyn TableLike)> {
+    let mut tables: Vec<&dyn TableLike> = Vec::new();
+    for key in DEPENDENCY_TABLES {
+        if let Some(table) = doc.get(key).and_then(Item::as_table_like) {
+            tables.push(table);
+        }
+    }
+    if let Some(table) = doc
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"))
+        .and_then(Item::as_table_like)
+    {
+        tables.push(table);
+    }
+    if let Some(targets) = doc.get("target").and_then(Item::as_table_like) {
+        for (_, target) in targets.iter() {
+            for key in DEPENDENCY_TABLES {
+                if let Some(table) = target.get(key).and_then(Item::as_table_like) {
+                    tables.push(table);
+                }
+            }
+        }
+    }
+    if let Some(patches) = doc.get("patch").and_then(Item::as_table_like) {
+        for (_, patch) in patches.iter() {
+            if let Some(table) = patch.as_table_like() {
+                tables.push(table);
+            }
+        }
+    }
+
+    tables
+        .into_iter()
+        .flat_map(|table| table.iter())
+        .filter_map(|(name, dep)| dep.as_table_like().map(|dep| (name.to_string(), dep)))
+        .collect()
+}
+
+/// Check foundry.toml paths in every profile
+fn check_foundry_config(
+    root: &Path,
+    foundry: &Path,
+    problems: &mut Vec<Problem>,
+) -> Result<(), String> {
+    let doc = read_manifest(foundry)?;
+    let Some(profiles) = doc.get("profile").and_then(Item::as_table_like) else {
+        return Ok(());
+    };
+
+    for (profile_name, profile) in profiles.iter() {
+        let Some(profile) = profile.as_table_like() else {
+            continue;
+        };
+        let mut paths = Vec::new();
+        for (key, item) in profile.iter() {
+            if !FOUNDRY_PATH_KEYS.contains(&key) {
+                continue;
+            }
+            match item.as_value() {
+                Some(Value::String(path)) => paths.push((key, path.value().to_string())),
+                Some(Value::Array(array)) => {
+                    for entry in array.iter() {
+                        match entry {
+                            Value::String(s) if key == "remappings" => {
+                                if let Some((_, target)) = s.value().split_once('=') {
+                                    paths.push((key, target.to_string()));
+                                }
+                            }
+                            Value::String(s) => paths.push((key, s.value().to_string())),
+                            Value::InlineTable(table) => {
+                                if let Some(path) = table.get("path").and_then(Value::as_str) {
+                                    paths.push((key, path.to_string()));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (key, path) in paths {
+            if !normalize(&root.join(&path)).starts_with(root) {
+                problems.push(Problem {
+                    file: foundry.to_path_buf(),
+                    message: format!(
+                        "[profile.{}] {} points to \"{}\", which is outside the project",
+                        profile_name, key, path
+                    ),
+                    fix: "point it into lib/ (e.g. lib/risc0-ethereum/...) or remove the entry"
+                        .to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check remappings.txt targets stay inside the project and exist
+fn check_remappings(
+    root: &Path,
+    remappings_path: &Path,
+    problems: &mut Vec<Problem>,
+) -> Result<(), String> {
+    let content = fs::read_to_string(remappings_path)
+        .map_err(|e| format!("Failed to read remappings.txt: {}", e))?;
+    let remappings = Remappings::parse(&content)?;
+
+    for remapping in &remappings.0 {
+        if !normalize(&root.join(&remapping.target)).starts_with(root) {
+            problems.push(Problem {
+                file: remappings_path.to_path_buf(),
+                message: format!("{} points outside the project", remapping),
+                fix: format!(
+                    "remap {} into lib/, e.g. {}=lib/<library>/...",
+                    remapping.prefix, remapping.prefix
+                ),
+            });
+        }
+    }
+    for remapping in missing_targets(root, &remappings) {
+        if normalize(&root.join(&remapping.target)).starts_with(root) {
+            problems.push(Problem {
+                file: remappings_path.to_path_buf(),
+                message: format!("{} points to a directory that does not exist", remapping),
+                fix: "install the library (git submodule update --init --recursive) or fix the target"
+                    .to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Check submodules and locked libraries are present
+fn check_libraries(root: &Path, problems: &mut Vec<Problem>) -> Result<(), String> {
+    let gitmodules = root.join(".gitmodules");
+    if let Ok(content) = fs::read_to_string(&gitmodules) {
+        for line in content.lines() {
+            let Some(path) = line
+                .trim()
+                .strip_prefix("path")
+                .and_then(|rest| rest.trim_start().strip_prefix('='))
+                .map(str::trim)
+            else {
+                continue;
+            };
+            if is_empty_dir(&root.join(path)) {
+                problems.push(Problem {
+                    file: gitmodules.clone(),
+                    message: format!("submodule {} is missing or empty", path),
+                    fix: "run `git submodule update --init --recursive`".to_string(),
+                });
+            }
+        }
+    }
+
+    let lock = root.join(LOCK_FILE);
+    if lock.exists() {
+        let doc = read_manifest(&lock)?;
+        if let Some(libraries) = doc.get("library").and_then(Item::as_array_of_tables) {
+            for library in libraries.iter() {
+                let Some(path) = library.get("path").and_then(Item::as_str) else {
+                    continue;
+                };
+                if is_empty_dir(&root.join(path)) {
+                    problems.push(Problem {
+                        file: lock.clone(),
+                        message: format!("locked library {} is missing or empty", path),
+                        fix: "reinstall the libraries, e.g. `git submodule update --init --recursive` or `forge soldeer install`"
+                            .to_string(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check Cargo's risc0-ethereum references agree with lib/risc0-ethereum
+fn check_risc0_refs(
+    root: &Path,
+    risc0_refs: &[(PathBuf, String, String)],
+    problems: &mut Vec<Problem>,
+) {
+    let lib_version = read_manifest(&root.join("lib/risc0-ethereum/Cargo.toml"))
+        .ok()
+        .and_then(|doc| {
+            doc.get("workspace")
+                .and_then(|w| w.get("package"))
+                .and_then(|p| p.get("version"))
+                .and_then(Item::as_str)
+                .map(str::to_string)
+        });
+    let lib_release = lib_version.as_deref().and_then(release_of);
+
+    let mut first: Option<(&str, &str)> = None;
+    for (manifest, name, reference) in risc0_refs {
+        let Some(release) = release_of(reference) else {
+            continue;
+        };
+
+        if let Some(lib_release) = &lib_release {
+            if *lib_release != release {
+                problems.push(Problem {
+                    file: manifest.clone(),
+                    message: format!(
+                        "`{}` uses risc0-ethereum {} but lib/risc0-ethereum is version {}",
+                        name,
+                        reference,
+                        lib_version.as_deref().unwrap_or_default()
+                    ),
+                    fix: format!(
+                        "use tag \"v{}\" in Cargo.toml, or check out release-{} in lib/risc0-ethereum",
+                        lib_version.as_deref().unwrap_or_default(),
+                        release
+                    ),
+                });
+            }
+        }
+
+        match first {
+            None => first = Some((name, reference)),
+            Some((first_name, first_ref)) if release_of(first_ref) != Some(release.clone()) => {
+                problems.push(Problem {
+                    file: manifest.clone(),
+                    message: format!(
+                        "`{}` uses risc0-ethereum {} but `{}` uses {}",
+                        name, reference, first_name, first_ref
+                    ),
+                    fix: "use the same risc0-ethereum release for every dependency".to_string(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// The `major.minor` release named by a ref like `release-1.3` or `v1.3.0`
+///
+/// Commit hashes name no release.
+fn release_of(reference: &str) -> Option<String> {
+    if is_commit(reference) {
+        return None;
+    }
+    let start = reference.find(|c: char| c.is_ascii_digit())?;
+    let mut parts = reference[start..].split(|c: char| !c.is_ascii_digit());
+    let major = parts.next().filter(|p| !p.is_empty())?;
+    let minor = parts.next().filter(|p| !p.is_empty())?;
+    Some(format!("{}.{}", major, minor))
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).map_or(true, |mut entries| entries.next().is_none())
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_of_branches_tags_and_versions() {
+        assert_eq!(release_of("release-1.3").as_deref(), Some("1.3"));
+        assert_eq!(release_of("v1.3.0").as_deref(), Some("1.3"));
+        assert_eq!(release_of("v2.0.0-rc.1").as_deref(), Some("2.0"));
+        assert_eq!(release_of("1.2.1").as_deref(), Some("1.2"));
+    }
+
+    #[test]
+    fn release_of_ignores_refs_without_a_release() {
+        assert_eq!(release_of("main"), None);
+        assert_eq!(release_of("v2"), None);
+        assert_eq!(release_of("3f1a2b4c5d6e7f8091a2b3c4d5e6f708192a3b4c"), None);
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::CHECK_MARK;
+use crate::build::run_streamed;
+use crate::environment::{load_vars, var};
+use crate::image_id::{contract_image_ids, DEPLOYMENTS_DIR};
+use crate::profile::{Config, Profile};
+use crate::rpc;
+
+/// Forge script deploying the template contracts
+pub const DEPLOY_SCRIPT: &str = "script/DeployCounter.s.sol";
+
+/// Mock verifier accepting the fake receipts of dev mode
+pub const MOCK_VERIFIER: &str =
+    "lib/risc0-ethereum/contracts/src/test/RiscZeroMockVerifier.sol:RiscZeroMockVerifier";
+
+/// Seal selector risc0-ethereum encodes fake receipts with
+pub const MOCK_SELECTOR: &str = "0xFFFFFFFF";
+
+/// Contracts deployed to one chain, stored in `deployments/<chain-id>.json`
+#[derive(Serialize, Deserialize)]
+pub struct Deployment {
+    pub chain_id: u64,
+    pub profile: String,
+    pub contracts: BTreeMap<String, DeployedContract>,
+    /// Image IDs from ImageID.sol the contracts were compiled with
+    pub image_ids: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeployedContract {
+    pub address: String,
+    pub tx_hash: Option<String>,
+}
+
+/// `berry deploy`: run the deploy script against a profile's chain and record the result
+pub fn run_deploy(dir: Option<&str>, profile: Option<&str>) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let config = Config::load(dir)?;
+    let profile = config.profile(profile.unwrap_or(config.active_profile()))?;
+    let mut vars = load_vars(dir, Some(&profile.name))?;
+    let rpc_url = var(&vars, "ETH_RPC_URL")?.to_string();
+
+    let chain_id = rpc::chain_id(&rpc_url).map_err(|e| {
+        format!(
+            "{} (start a local node with `berry node start`)",
+            e.trim_end_matches('.')
+        )
+    })?;
+    if chain_id != profile.chain_id {
+        return Err(format!(
+            "{} reports chain ID {}, profile '{}' expects {}",
+            rpc_url, chain_id, profile.name, profile.chain_id
+        ));
+    }
+
+    let contracts = deploy_contracts(
+        dir,
+        &profile,
+        &rpc_url,
+        chain_id,
+        &mut vars,
+        |task, command| run_streamed(dir, "forge", task, command).map(|output| output.join("\n")),
+    )?;
+
+    let (path, deployment) = record_deployment(dir, &profile, chain_id, contracts)?;
+    println!(
+        "\n{} Recorded {} contract(s) in {}",
+        CHECK_MARK,
+        deployment.contracts.len(),
+        path.strip_prefix(dir).unwrap_or(&path).display()
+    );
+    for (key, value) in deployment_vars(&deployment) {
+        println!("  {}={}", key, value);
+    }
+    println!("\nLoad them into your shell with `eval \"$(berry env)\"`");
+    Ok(())
+}
+
+/// Deploy the mock verifier when dev mode needs one, then run the deploy script
+///
+/// `run` executes each forge command under a task name and returns its stdout. The
+/// mock's address is set as `VERIFIER_ADDRESS` in `vars` for the steps that follow.
+pub fn deploy_contracts(
+    dir: &Path,
+    profile: &Profile,
+    rpc_url: &str,
+    chain_id: u64,
+    vars: &mut Vec<(String, String)>,
+    mut run: impl FnMut(&str, &mut Command) -> Result<String, String>,
+) -> Result<BTreeMap<String, DeployedContract>, String> {
+    let private_key = var(vars, "ETH_WALLET_PRIVATE_KEY")?.to_string();
+    // Dev mode runs without network access and verifies fake receipts with a mock
+    let offline: &[&str] = if profile.fake_receipts() {
+        &["--offline"]
+    } else {
+        &[]
+    };
+    let mut contracts = BTreeMap::new();
+    if profile.fake_receipts() && profile.verifier_address.is_none() {
+        let output = run(
+            "verifier",
+            Command::new("forge")
+                .args(["create", MOCK_VERIFIER, "--broadcast", "--rpc-url", rpc_url])
+                .args(["--private-key", &private_key])
+                .args(offline)
+                .args(["--constructor-args", MOCK_SELECTOR]),
+        )?;
+        let verifier = created_contract(&output)?;
+        vars.retain(|(key, _)| key != "VERIFIER_ADDRESS");
+        vars.push(("VERIFIER_ADDRESS".to_string(), verifier.address.clone()));
+        contracts.insert(contract_name(MOCK_VERIFIER).to_string(), verifier);
+    }
+
+    run(
+        "deploy",
+        Command::new("forge")
+            .args(["script", DEPLOY_SCRIPT, "--rpc-url", rpc_url, "--broadcast"])
+            .args(["--private-key", &private_key])
+            .args(offline)
+            .envs(vars.iter().cloned()),
+    )?;
+    contracts.extend(read_broadcast(dir, DEPLOY_SCRIPT, chain_id)?);
+    Ok(contracts)
+}
+
+/// Contracts created by a forge script run, read from its `run-latest.json` broadcast
+///
+/// A contract deployed more than once keeps its last address.
+pub fn read_broadcast(
+    dir: &Path,
+    script: &str,
+    chain_id: u64,
+) -> Result<BTreeMap<String, DeployedContract>, String> {
+    let broadcast = dir
+        .join("broadcast")
+        .join(Path::new(script).file_name().unwrap_or_default())
+        .join(chain_id.to_string())
+        .join("run-latest.json");
+    let content = fs::read_to_string(&broadcast)
+        .map_err(|e| format!("Failed to read {}: {}", broadcast.display(), e))?;
+    let run: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", broadcast.display(), e))?;
+
+    let contracts: BTreeMap<String, DeployedContract> = run["transactions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|tx| {
+            tx["transactionType"]
+                .as_str()
+                .unwrap_or("CREATE")
+                .starts_with("CREATE")
+        })
+        .filter_map(|tx| {
+            let contract = DeployedContract {
+                address: tx["contractAddress"].as_str()?.to_string(),
+                tx_hash: tx["hash"].as_str().map(str::to_string),
+            };
+            Some((tx["contractName"].as_str()?.to_string(), contract))
+        })
+        .collect();
+    if contracts.is_empty() {
+        return Err(format!("No deployed contracts in {}", broadcast.display()));
+    }
+    Ok(contracts)
+}
+
+/// Write `deployments/<chain-id>.json`, replacing the previous record for the chain
+pub fn record_deployment(
+    dir: &Path,
+    profile: &Profile,
+    chain_id: u64,
+    contracts: BTreeMap<String, DeployedContract>,
+) -> Result<(PathBuf, Deployment), String> {
+    let path = deployment_path(dir, chain_id);
+    let deployment = write_deployment(dir, &path, profile, chain_id, contracts)?;
+    Ok((path, deployment))
+}
+
+/// Write a deployment record to `path`, with the image IDs the contracts were built with
+pub fn write_deployment(
+    dir: &Path,
+    path: &Path,
+    profile: &Profile,
+    chain_id: u64,
+    contracts: BTreeMap<String, DeployedContract>,
+) -> Result<Deployment, String> {
+    let deployment = Deployment {
+        chain_id,
+        profile: profile.name.clone(),
+        contracts,
+        image_ids: contract_image_ids(dir)?,
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(&deployment)
+        .map_err(|e| format!("Failed to serialize the deployment: {}", e))?;
+    fs::write(path, content + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(deployment)
+}
+
+/// The recorded deployment for a chain, if there is one
+pub fn load_deployment(dir: &Path, chain_id: u64) -> Result<Option<Deployment>, String> {
+    let path = deployment_path(dir, chain_id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// `<CONTRACT>_ADDRESS` and `<NAME>_IMAGE_ID` variables for a deployment
+pub fn deployment_vars(deployment: &Deployment) -> Vec<(String, String)> {
+    let addresses = deployment.contracts.iter().map(|(name, contract)| {
+        (
+            format!("{}_ADDRESS", env_name(name)),
+            contract.address.clone(),
+        )
+    });
+    let image_ids = deployment
+        .image_ids
+        .iter()
+        .map(|(name, id)| (format!("{}_IMAGE_ID", env_name(name)), id.clone()));
+    addresses.chain(image_ids).collect()
+}
+
+/// Address and transaction hash `forge create` reports
+pub fn created_contract(output: &str) -> Result<DeployedContract, String> {
+    let field = |label: &str| {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(label))
+            .map(|value| value.trim().to_string())
+    };
+    Ok(DeployedContract {
+        address: field("Deployed to:")
+            .ok_or_else(|| "forge create did not report the contract address".to_string())?,
+        tx_hash: field("Transaction hash:"),
+    })
+}
+
+/// Contract name of a `path:Name` identifier
+pub fn contract_name(identifier: &str) -> &str {
+    identifier.rsplit(':').next().unwrap_or(identifier)
+}
+
+fn deployment_path(dir: &Path, chain_id: u64) -> PathBuf {
+    dir.join(DEPLOYMENTS_DIR).join(format!("{}.json", chain_id))
+}
+
+/// `ERC20FixedSupply` -> `ERC20_FIXED_SUPPLY`
+fn env_name(name: &str) -> String {
+    let mut result = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_uppercase()
+            && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+        {
+            result.push('_');
+        }
+        result.push(if c.is_ascii_alphanumeric() {
+            c.to_ascii_uppercase()
+        } else {
+            '_'
+        });
+        previous = Some(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_names_are_upper_snake_case() {
+        assert_eq!(env_name("ERC20FixedSupply"), "ERC20_FIXED_SUPPLY");
+        assert_eq!(env_name("Counter"), "COUNTER");
+        assert_eq!(env_name("RiscZeroMockVerifier"), "RISC_ZERO_MOCK_VERIFIER");
+        assert_eq!(env_name("BALANCE_OF"), "BALANCE_OF");
+        assert_eq!(env_name("is-even"), "IS_EVEN");
+    }
+
+    #[test]
+    fn reads_forge_create_output() {
+        let output = "Deployer: 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266\nDeployed to: 0x5FbDB2315678afecb367f032d93F642f64180aa3\nTransaction hash: 0xabc\n";
+        let contract = created_contract(output).unwrap();
+        assert_eq!(
+            contract.address,
+            "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+        );
+        assert_eq!(contract.tx_hash.as_deref(), Some("0xabc"));
+        assert!(created_contract("Compiler run successful").is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use super::{new_spinner, CHECK_MARK, CROSS_MARK};
+use crate::address::parse_address;
+use crate::deploy::{deploy_contracts, write_deployment, DEPLOY_SCRIPT};
+use crate::environment::{add_to_gitignore, load_vars, var};
+use crate::node;
+use crate::profile::{Config, Profile, Prover};
+use crate::rpc::{self, RpcError};
+
+/// Contract names in the deploy script's broadcast output
+const TOKEN_CONTRACT: &str = "ERC20FixedSupply";
+const COUNTER_CONTRACT: &str = "Counter";
+
+/// Counter value expected after one successful publish
+const EXPECTED_COUNT: u64 = 1;
+
+/// Structured log of every step attempt, relative to the project root
+const E2E_LOG: &str = ".berry/logs/e2e.jsonl";
+
+/// Contracts deployed by the last run, relative to the project root
+const E2E_DEPLOYMENT: &str = ".berry/e2e-deployment.json";
+
+/// Default JUnit report location, relative to the project root
+const JUNIT_REPORT: &str = ".berry/e2e-junit.xml";
+
+/// How often a step failing with a transient error is attempted
+const ATTEMPTS: u32 = 3;
+
+/// Steps of the flow, in order; in dev mode deploy also creates a mock verifier
+const STEPS: &[&str] = &["node", "deploy", "fund", "publish", "assert"];
+
+/// Options for `berry e2e`
+pub struct E2eOptions<'a> {
+    pub dir: Option<&'a str>,
+    pub profile: Option<&'a str>,
+    pub junit: Option<&'a str>,
+    pub keep_node: bool,
+    /// Account whose token balance the publisher proves
+    pub account: Option<&'a str>,
+}
+
+/// Why a step attempt failed, and whether attempting it again may succeed
+struct StepError {
+    message: String,
+    transient: bool,
+}
+
+impl From<String> for StepError {
+    fn from(message: String) -> Self {
+        StepError {
+            message,
+            transient: false,
+        }
+    }
+}
+
+impl From<RpcError> for StepError {
+    fn from(error: RpcError) -> Self {
+        StepError {
+            message: error.message,
+            transient: error.transient,
+        }
+    }
+}
+
+/// Outcome of one step
+struct StepResult {
+    name: &'static str,
+    duration: Duration,
+    attempts: u32,
+    error: Option<String>,
+}
+
+/// Runs the steps, timing and logging each attempt
+struct Runner<'a> {
+    dir: &'a Path,
+    log: File,
+    results: Vec<StepResult>,
+}
+
+impl Runner<'_> {
+    /// Run a step, retrying it while it fails with a transient error
+    ///
+    /// Only errors of requests to the node are transient, so steps that broadcast
+    /// transactions through forge, cast or the publisher are never repeated.
+    fn step<T>(
+        &mut self,
+        name: &'static str,
+        mut f: impl FnMut(&Path) -> Result<T, StepError>,
+    ) -> Result<T, String> {
+        let pb = new_spinner(format!("{}...", name));
+        let started = Instant::now();
+        let mut attempt = 1;
+        let result = loop {
+            let attempt_started = Instant::now();
+            let result = f(self.dir);
+            self.log_event(json!({
+                "step": name,
+                "attempt": attempt,
+                "status": if result.is_ok() { "passed" } else { "failed" },
+                "duration_ms": attempt_started.elapsed().as_millis() as u64,
+                "error": result.as_ref().err().map(|e| &e.message),
+            }));
+            match result {
+                Err(e) if attempt < ATTEMPTS && e.transient => {
+                    pb.println(format!(
+                        "  {} {} attempt {} failed, retrying: {}",
+                        CROSS_MARK, name, attempt, e.message
+                    ));
+                    thread::sleep(Duration::from_secs(1 << attempt));
+                    attempt += 1;
+                }
+                result => break result.map_err(|e| e.message),
+            }
+        };
+
+        let duration = started.elapsed();
+        match &result {
+            Ok(_) => pb.finish_with_message(format!(
+                "{} {} ({:.1}s)",
+                CHECK_MARK,
+                name,
+                duration.as_secs_f64()
+            )),
+            Err(e) => pb.finish_with_message(format!(
+                "{} {} ({:.1}s): {}",
+                CROSS_MARK,
+                name,
+                duration.as_secs_f64(),
+                e
+            )),
+        }
+        self.results.push(StepResult {
+            name,
+            duration,
+            attempts: attempt,
+            error: result.as_ref().err().cloned(),
+        });
+        result
+    }
+
+    fn log_event(&mut self, mut event: Value) {
+        event["timestamp_ms"] = json!(std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64));
+        let _ = writeln!(self.log, "{}", event);
+    }
+}
+
+/// `berry e2e`: deploy, publish and check the result against a node
+pub fn run_e2e(options: &E2eOptions) -> Result<(), String> {
+    let dir = Path::new(options.dir.unwrap_or("."));
+    let config = Config::load(dir)?;
+    let profile = config.profile(options.profile.unwrap_or(config.active_profile()))?;
+    let account = options.account.map(parse_address).transpose()?;
+    if profile.prover == Prover::Bonsai
+        && env::var("BONSAI_API_KEY").is_err()
+        && !load_vars(dir, Some(&profile.name))?
+            .iter()
+            .any(|(key, _)| key == "BONSAI_API_KEY")
+    {
+        return Err(format!(
+            "Profile '{}' proves with Bonsai but BONSAI_API_KEY is not set; use `berry setup --prover dev` to test without it",
+            profile.name
+        ));
+    }
+    if let Some(url) = profile.bonsai_api_url.as_deref() {
+        if profile.uses_bonsai_mock() && ureq::get(format!("{}/version", url)).call().is_err() {
+            return Err(format!(
+                "Profile '{}' proves with the Bonsai mock at {}, which is not running; start it with `berry bonsai-mock`",
+                profile.name, url
+            ));
+        }
+    }
+
+    let log_path = dir.join(E2E_LOG);
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    add_to_gitignore(dir, "berry runtime state and logs", &[".berry/"])?;
+    let log =
+        File::create(&log_path).map_err(|e| format!("Failed to create {}: {}", E2E_LOG, e))?;
+    let mut runner = Runner {
+        dir,
+        log,
+        results: Vec::new(),
+    };
+
+    println!(
+        "\nRunning end-to-end test against profile '{}' with the {} prover...",
+        profile.name, profile.prover
+    );
+    let started_node = profile.is_local() && node::running_node(dir).is_none();
+    let result = run_steps(&mut runner, &profile, account.as_deref());
+
+    // Teardown never hides the outcome: the report is always written, then the
+    // first error is returned
+    let stopped = if started_node && !options.keep_node {
+        node::stop_node(options.dir)
+    } else {
+        Ok(())
+    };
+    let junit = options
+        .junit
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dir.join(JUNIT_REPORT));
+    let reported = write_junit(&junit, STEPS, &runner.results);
+
+    let total: Duration = runner.results.iter().map(|r| r.duration).sum();
+    match &result {
+        Ok(()) => println!(
+            "\n{} End-to-end test passed in {:.1}s",
+            CHECK_MARK,
+            total.as_secs_f64()
+        ),
+        Err(_) => println!(
+            "\n{} End-to-end test failed after {:.1}s",
+            CROSS_MARK,
+            total.as_secs_f64()
+        ),
+    }
+    println!("Log: {}", E2E_LOG);
+    println!("JUnit report: {}", junit.display());
+    result.and(stopped).and(reported)
+}
+
+fn run_steps(runner: &mut Runner, profile: &Profile, account: Option<&str>) -> Result<(), String> {
+    let name = profile.name.as_str();
+    let (mut vars, chain_id) = runner.step("node", |dir| {
+        if profile.is_local() && node::running_node(dir).is_none() {
+            node::start_node(dir.to_str(), Some(name))?;
+        }
+        let vars = load_vars(dir, Some(name))?;
+        let result = rpc::request(var(&vars, "ETH_RPC_URL")?, "eth_chainId", json!([]))?;
+        let chain_id = rpc::quantity(&result)?;
+        Ok((vars, chain_id))
+    })?;
+    let rpc_url = var(&vars, "ETH_RPC_URL")?.to_string();
+
+    // Dev mode builds the publisher without network access
+    let offline: &[&str] = if profile.fake_receipts() {
+        &["--offline"]
+    } else {
+        &[]
+    };
+    let (token, counter, account) = runner.step("deploy", |dir| {
+        let contracts = deploy_contracts(
+            dir,
+            profile,
+            &rpc_url,
+            chain_id,
+            &mut vars,
+            |task, command| run_logged(dir, task, command),
+        )?;
+        let address = |name: &str| {
+            contracts
+                .get(name)
+                .map(|contract| contract.address.clone())
+                .ok_or_else(|| format!("{} not found in the {} broadcast", name, DEPLOY_SCRIPT))
+        };
+        let token = address(TOKEN_CONTRACT)?;
+        let counter = address(COUNTER_CONTRACT)?;
+        // The profile's user account, or the wallet itself, unless one is given
+        let account = match account {
+            Some(account) => account.to_string(),
+            None => var(&vars, "USER_ADDRESS")
+                .or_else(|_| var(&vars, "ETH_WALLET_ADDRESS"))?
+                .to_string(),
+        };
+        // Kept apart from the user's deployments/<chain-id>.json
+        write_deployment(dir, &dir.join(E2E_DEPLOYMENT), profile, chain_id, contracts)?;
+        Ok((token, counter, account))
+    })?;
+
+    runner.step("fund", |dir| {
+        run_logged(
+            dir,
+            "fund",
+            Command::new("cast")
+                .args(["send", "--rpc-url", &rpc_url])
+                .args(["--private-key", var(&vars, "ETH_WALLET_PRIVATE_KEY")?])
+                .args([&token, "transfer(address,uint256)", &account, "100"]),
+        )?;
+        Ok(())
+    })?;
+
+    runner.step("publish", |dir| {
+        run_logged(
+            dir,
+            "publish",
+            Command::new("cargo")
+                .arg("run")
+                .args(offline)
+                .args(["--bin", "publisher", "--"])
+                .arg(format!(
+                    "--eth-wallet-private-key={}",
+                    var(&vars, "ETH_WALLET_PRIVATE_KEY")?
+                ))
+                .arg(format!("--eth-rpc-url={}", rpc_url))
+                .arg(format!("--counter={}", counter))
+                .arg(format!("--token-contract={}", token))
+                .arg(format!("--account={}", account))
+                .envs(vars.iter().cloned()),
+        )?;
+        Ok(())
+    })?;
+
+    runner.step("assert", |_| {
+        let data = format!("0x{}", hex::encode(rpc::selector("get()")));
+        let result = rpc::request(
+            &rpc_url,
+            "eth_call",
+            json!([{ "to": counter, "data": data }, "latest"]),
+        )?;
+        let count = rpc::quantity(&result)?;
+        if count != EXPECTED_COUNT {
+            return Err(format!("Expected counter value {}, got {}", EXPECTED_COUNT, count).into());
+        }
+        Ok(())
+    })
+}
+
+/// Run a command, writing its output to `.berry/logs/e2e-<step>.log`
+///
+/// Returns the command's stdout.
+fn run_logged(dir: &Path, step: &str, command: &mut Command) -> Result<String, String> {
+    let log_name = format!(".berry/logs/e2e-{}.log", step);
+    let output = command
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run {:?}: {}", command.get_program(), e))?;
+    let mut log = output.stdout.clone();
+    log.extend_from_slice(&output.stderr);
+    fs::write(dir.join(&log_name), &log)
+        .map_err(|e| format!("Failed to write {}: {}", log_name, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(5).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(format!(
+            "{:?} exited with {} ({}), see {}",
+            command.get_program(),
+            output.status,
+            tail.join(" | "),
+            log_name
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Write the step results as a JUnit XML test suite
+fn write_junit(path: &Path, steps: &[&str], results: &[StepResult]) -> Result<(), String> {
+    let failures = results.iter().filter(|r| r.error.is_some()).count();
+    let skipped = steps.len() - results.len();
+    let total: Duration = results.iter().map(|r| r.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuite name=\"berry-e2e\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        steps.len(),
+        failures,
+        skipped,
+        total.as_secs_f64()
+    ));
+    for step in steps {
+        let result = results.iter().find(|r| r.name == *step);
+        let time = result.map_or(0.0, |r| r.duration.as_secs_f64());
+        xml.push_str(&format!(
+            "  <testcase classname=\"berry.e2e\" name=\"{}\" time=\"{:.3}\">",
+            step, time
+        ));
+        match result {
+            Some(StepResult {
+                error: Some(error),
+                attempts,
+                ..
+            }) => xml.push_str(&format!(
+                "\n    <failure message=\"{}\">failed after {} attempt(s)</failure>\n  ",
+                escape_xml(error),
+                attempts
+            )),
+            Some(_) => {}
+            None => xml.push_str("<skipped/>"),
+        }
+        xml.push_str("</testcase>\n");
+    }
+    xml.push_str("</testsuite>\n");
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, xml).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,659 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::ValueEnum;
+
+use super::{CHECK_MARK, CROSS_MARK};
+use crate::accounts::{derive, is_anvil_key, profile_mnemonic};
+use crate::address::{address_of, check_pair, parse_address};
+use crate::deploy::{deployment_vars, load_deployment};
+use crate::node::running_node;
+use crate::profile::{Config, Profile, Prover, WalletSource, CONFIG_FILE};
+use crate::secrets::{resolve_wallet, role_accounts, ANVIL_ADDRESS, ANVIL_PRIVATE_KEY};
+
+/// The env file `source env.sh` picks up: a copy of the active profile's file
+pub const ACTIVE_ENV_FILE: &str = "env.sh";
+
+/// Lines added to the project .gitignore so env files are never committed
+const IGNORED_ENV_FILES: &[&str] = &["env.sh", "env.*.sh"];
+
+/// Environment variables exported for a profile, in file order, without the wallet
+pub fn profile_vars(profile: &Profile) -> Vec<(&'static str, String)> {
+    let mut vars = vec![
+        ("BERRY_PROFILE", profile.name.clone()),
+        ("ETH_RPC_URL", profile.rpc_url.clone()),
+        ("CHAIN_ID", profile.chain_id.to_string()),
+    ];
+
+    // Lets forge and cast use the keystore account directly
+    if let WalletSource::Foundry(account) = &profile.wallet {
+        vars.push(("ETH_KEYSTORE_ACCOUNT", account.clone()));
+    }
+
+    match profile.prover {
+        Prover::Dev => vars.push(("RISC0_DEV_MODE", "1".to_string())),
+        Prover::Local => {
+            vars.push(("RISC0_DEV_MODE", "0".to_string()));
+            vars.push(("RISC0_PROVER", "local".to_string()));
+        }
+        Prover::Bonsai => {
+            // The mock's fake receipts only verify in dev mode; RISC0_PROVER still picks Bonsai
+            let dev_mode = if profile.uses_bonsai_mock() { "1" } else { "0" };
+            vars.push(("RISC0_DEV_MODE", dev_mode.to_string()));
+            vars.push(("RISC0_PROVER", "bonsai".to_string()));
+            if let Some(url) = &profile.bonsai_api_url {
+                vars.push(("BONSAI_API_URL", url.clone()));
+            }
+            // The SDK requires a key, the mock ignores it
+            if profile.uses_bonsai_mock() {
+                vars.push(("BONSAI_API_KEY", "berry-bonsai-mock".to_string()));
+            }
+        }
+    }
+    if let Some(address) = &profile.verifier_address {
+        vars.push(("VERIFIER_ADDRESS", address.clone()));
+    }
+    vars
+}
+
+/// Write `env.<profile>.sh` into the project
+///
+/// Only anvil's dev key is written by default; other keys stay in their secret
+/// source and are injected by `berry run` and `berry env` unless `write_key` is set.
+pub fn write_profile_env(
+    dir: &Path,
+    profile: &Profile,
+    write_key: bool,
+) -> Result<PathBuf, String> {
+    let mut content = format!("# Generated by berry for profile '{}'\n", profile.name);
+    for (key, value) in profile_vars(profile) {
+        content.push_str(&(Shell::Bash.assignment(key, &value) + "\n"));
+    }
+
+    // Dev keys are public anyway; other keys are only written when asked to
+    let wallet = match profile.wallet {
+        WalletSource::Anvil | WalletSource::Mnemonic(_) => Some(resolve_wallet(profile)?),
+        _ if write_key => Some(resolve_wallet(profile)?),
+        _ => None,
+    };
+    let mut accounts = vec![("ETH_WALLET".to_string(), wallet)];
+    for (role, wallet) in role_accounts(profile)? {
+        accounts.push((role, Some(wallet)));
+    }
+
+    let mut secret_written = false;
+    let mut secret_kept = false;
+    for (prefix, wallet) in accounts {
+        match wallet {
+            Some(wallet) if write_key || is_anvil_key(&wallet.private_key) => {
+                secret_written |= !is_anvil_key(&wallet.private_key);
+                content.push_str(
+                    &(Shell::Bash.assignment(&format!("{}_ADDRESS", prefix), &wallet.address)
+                        + "\n"),
+                );
+                content.push_str(
+                    &(Shell::Bash
+                        .assignment(&format!("{}_PRIVATE_KEY", prefix), &wallet.private_key)
+                        + "\n"),
+                );
+            }
+            Some(wallet) => {
+                secret_kept = true;
+                content.push_str(
+                    &(Shell::Bash.assignment(&format!("{}_ADDRESS", prefix), &wallet.address)
+                        + "\n"),
+                );
+            }
+            None => {
+                secret_kept = true;
+                if let Some(address) = known_address(profile)? {
+                    content.push_str(
+                        &(Shell::Bash.assignment(&format!("{}_ADDRESS", prefix), &address) + "\n"),
+                    );
+                }
+            }
+        }
+    }
+
+    if secret_written {
+        eprintln!(
+            "{} Warning: writing non-dev private keys of profile '{}' ({}) to {} in cleartext",
+            CROSS_MARK,
+            profile.name,
+            profile.wallet,
+            profile.env_file()
+        );
+    }
+    if secret_kept {
+        content.push_str(&format!(
+            "\n# Private keys come from {}; load them with `berry run -- <command>` or `eval \"$(berry env)\"`\n",
+            profile.wallet
+        ));
+    }
+
+    if profile.fake_receipts() && profile.verifier_address.is_none() {
+        content.push_str(
+            "\n# Dev mode: receipts are fake and only pass a RiscZeroMockVerifier, which `berry e2e` deploys\n",
+        );
+    }
+    if profile.uses_bonsai_mock() {
+        content.push_str("# Start the Bonsai stand-in with `berry bonsai-mock`\n");
+    } else if profile.prover == Prover::Bonsai && env::var("BONSAI_API_KEY").is_err() {
+        content.push_str("\n# Get your Bonsai API key from https://bonsai.xyz/apply\n");
+        content.push_str("# export BONSAI_API_KEY=your_api_key_here\n");
+    }
+
+    let path = dir.join(profile.env_file());
+    write_private(&path, &content)?;
+    add_to_gitignore(
+        dir,
+        "Environment files may contain private keys",
+        IGNORED_ENV_FILES,
+    )?;
+    Ok(path)
+}
+
+/// The wallet address of a profile, if it is known without decrypting a keystore
+fn known_address(profile: &Profile) -> Result<Option<String>, String> {
+    if let Some(address) = &profile.wallet_address {
+        return Ok(Some(address.clone()));
+    }
+    match profile.wallet {
+        WalletSource::Anvil => Ok(Some(ANVIL_ADDRESS.to_string())),
+        WalletSource::Env if env::var("ETH_WALLET_PRIVATE_KEY").is_ok() => {
+            resolve_wallet(profile).map(|wallet| Some(wallet.address))
+        }
+        WalletSource::Env => env::var("ETH_WALLET_ADDRESS")
+            .ok()
+            .map(|address| parse_address(&address))
+            .transpose(),
+        WalletSource::Mnemonic(_) => resolve_wallet(profile).map(|wallet| Some(wallet.address)),
+        WalletSource::Foundry(_) | WalletSource::Keystore(_) => Ok(None),
+    }
+}
+
+/// Make a profile's env file the one `source env.sh` loads and remember the choice
+pub fn activate(dir: &Path, config: &mut Config, profile: &Profile) -> Result<(), String> {
+    let content = fs::read_to_string(dir.join(profile.env_file()))
+        .map_err(|e| format!("Failed to read {}: {}", profile.env_file(), e))?;
+    write_private(&dir.join(ACTIVE_ENV_FILE), &content)?;
+    config.set_active_profile(&profile.name);
+    config.save()
+}
+
+/// Write a file readable only by its owner
+fn write_private(path: &Path, content: &str) -> Result<(), String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict permissions of {}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+/// Add entries to the project .gitignore under a comment if they are not listed yet
+pub fn add_to_gitignore(dir: &Path, comment: &str, entries: &[&str]) -> Result<(), String> {
+    let path = dir.join(".gitignore");
+    let mut content = fs::read_to_string(&path).unwrap_or_default();
+    let listed: Vec<&str> = content.lines().map(str::trim).collect();
+    let missing: Vec<&str> = entries
+        .iter()
+        .copied()
+        .filter(|entry| {
+            !listed.contains(entry) && !listed.contains(&format!("/{}", entry).as_str())
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    if !content.is_empty() {
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push('\n');
+    }
+    content.push_str(&format!("# {}\n", comment));
+    for entry in missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    fs::write(&path, content).map_err(|e| format!("Failed to write .gitignore: {}", e))
+}
+
+/// `berry env use <profile>`: switch env.sh to another profile
+pub fn use_profile(dir: Option<&str>, name: &str) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    if !dir.is_dir() {
+        return Err(format!("Directory '{}' not found", dir.display()));
+    }
+
+    let mut config = Config::load(dir)?;
+    let profile = config.profile(name)?;
+    if !dir.join(profile.env_file()).exists() {
+        write_profile_env(dir, &profile, false)?;
+        println!("{} Generated {}", CHECK_MARK, profile.env_file());
+    }
+    activate(dir, &mut config, &profile)?;
+
+    println!(
+        "{} Switched to profile '{}' ({}, chain ID {})",
+        CHECK_MARK, profile.name, profile.rpc_url, profile.chain_id
+    );
+    println!("Run `source {}` to load it", ACTIVE_ENV_FILE);
+    Ok(())
+}
+
+/// Output syntax for `berry env`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Nu,
+    Dotenv,
+}
+
+impl Shell {
+    /// Guess the shell from $SHELL, defaulting to bash
+    fn detect() -> Self {
+        let shell = env::var("SHELL").unwrap_or_default();
+        match shell.rsplit('/').next() {
+            Some("zsh") => Shell::Zsh,
+            Some("fish") => Shell::Fish,
+            Some("nu") => Shell::Nu,
+            _ => Shell::Bash,
+        }
+    }
+
+    /// Render one variable assignment
+    fn assignment(self, key: &str, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => {
+                format!("export {}='{}'", key, value.replace('\'', "'\\''"))
+            }
+            Shell::Fish => format!(
+                "set -gx {} '{}'",
+                key,
+                value.replace('\\', "\\\\").replace('\'', "\\'")
+            ),
+            Shell::Nu => format!("$env.{} = \"{}\"", key, escape_double_quoted(value)),
+            Shell::Dotenv => format!("{}=\"{}\"", key, escape_double_quoted(value)),
+        }
+    }
+
+    /// How to load the output into the current session
+    fn usage(self) -> &'static str {
+        match self {
+            Shell::Bash | Shell::Zsh => "eval \"$(berry env)\"",
+            Shell::Fish => "berry env --shell fish | source",
+            Shell::Nu => "berry env --shell nu | save -f berry-env.nu; source berry-env.nu",
+            Shell::Dotenv => "berry env --shell dotenv > .env",
+        }
+    }
+}
+
+/// The environment of a profile: its generated env file if present, otherwise
+/// the variables it would be generated with
+///
+/// Keys whose secret source is unavailable are left out with a warning.
+pub fn load_vars(dir: &Path, profile: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let config = Config::load(dir)?;
+    let profile = config.profile(profile.unwrap_or(config.active_profile()))?;
+
+    let env_path = dir.join(profile.env_file());
+    let mut vars: Vec<(String, String)> = if env_path.exists() {
+        let content = fs::read_to_string(&env_path)
+            .map_err(|e| format!("Failed to read {}: {}", profile.env_file(), e))?;
+        content
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("export "))
+            .filter_map(|assignment| assignment.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), unquote(value.trim())))
+            .collect()
+    } else {
+        profile_vars(&profile)
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    };
+
+    // A node started by `berry node start` may listen on another port than configured
+    if let Some(node) = running_node(dir).filter(|node| node.profile == profile.name) {
+        vars.retain(|(key, _)| key != "ETH_RPC_URL");
+        vars.push(("ETH_RPC_URL".to_string(), node.rpc_url));
+    }
+
+    // Keys kept out of the env file are resolved from their secret source; without it
+    // the other variables are still handed out and commands needing the key fail later
+    let private_key = match var(&vars, "ETH_WALLET_PRIVATE_KEY").ok() {
+        Some(key) => Some(key.to_string()),
+        None => match resolve_wallet(&profile) {
+            Ok(wallet) => {
+                vars.push((
+                    "ETH_WALLET_PRIVATE_KEY".to_string(),
+                    wallet.private_key.clone(),
+                ));
+                Some(wallet.private_key)
+            }
+            Err(e) => {
+                eprintln!(
+                    "{} Warning: ETH_WALLET_PRIVATE_KEY is not set: {}",
+                    CROSS_MARK, e
+                );
+                None
+            }
+        },
+    };
+
+    // Never hand out an address that does not belong to the key
+    match (var(&vars, "ETH_WALLET_ADDRESS").ok(), &private_key) {
+        (Some(address), Some(private_key)) => {
+            check_pair(address, private_key).map_err(|e| {
+                format!(
+                    "{}: {}. Run `berry env check --fix` to correct it",
+                    profile.env_file(),
+                    e
+                )
+            })?;
+        }
+        (None, Some(private_key)) => {
+            vars.push(("ETH_WALLET_ADDRESS".to_string(), address_of(private_key)?))
+        }
+        (None, None) => {
+            if let Some(address) = known_address(&profile).ok().flatten() {
+                vars.push(("ETH_WALLET_ADDRESS".to_string(), address));
+            }
+        }
+        (Some(_), None) => {}
+    }
+
+    // Named accounts whose keys were kept out of the env file
+    let missing_role = profile.accounts.iter().any(|(role, _)| {
+        let key = format!("{}_PRIVATE_KEY", role.to_uppercase().replace('-', "_"));
+        var(&vars, &key).is_err()
+    });
+    if missing_role {
+        match role_accounts(&profile) {
+            Ok(accounts) => {
+                for (prefix, wallet) in accounts {
+                    for (suffix, value) in [
+                        ("ADDRESS", wallet.address),
+                        ("PRIVATE_KEY", wallet.private_key),
+                    ] {
+                        let key = format!("{}_{}", prefix, suffix);
+                        if var(&vars, &key).is_err() {
+                            vars.push((key, value));
+                        }
+                    }
+                }
+            }
+            Err(e) => eprintln!(
+                "{} Warning: keys of the named accounts are not set: {}",
+                CROSS_MARK, e
+            ),
+        }
+    }
+
+    // Contracts recorded by `berry deploy` for the profile's chain
+    if let Some(deployment) = load_deployment(dir, profile.chain_id)? {
+        for (key, value) in deployment_vars(&deployment) {
+            if var(&vars, &key).is_err() {
+                vars.push((key, value));
+            }
+        }
+    }
+    Ok(vars)
+}
+
+/// A variable loaded by `load_vars`
+pub fn var<'a>(vars: &'a [(String, String)], name: &str) -> Result<&'a str, String> {
+    vars.iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+        .ok_or_else(|| format!("{} is not set for this profile", name))
+}
+
+/// `berry env check`: find wallet addresses that do not match their keys
+pub fn check_env(dir: Option<&str>, fix: bool) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let mut problems = 0;
+
+    let mut env_files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name == ACTIVE_ENV_FILE || (name.starts_with("env.") && name.ends_with(".sh"))
+        })
+        .collect();
+    env_files.sort();
+    for path in env_files {
+        problems += check_env_file(&path, fix)?;
+    }
+
+    let mut config = Config::load(dir)?;
+    let mut changed = false;
+    for name in config.configured_profiles() {
+        let profile = match config.profile(&name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                println!("{} {}: {}", CROSS_MARK, CONFIG_FILE, e);
+                problems += 1;
+                continue;
+            }
+        };
+        let Some(address) = &profile.wallet_address else {
+            continue;
+        };
+        let private_key = match profile.wallet {
+            WalletSource::Anvil => ANVIL_PRIVATE_KEY.to_string(),
+            WalletSource::Env => match env::var("ETH_WALLET_PRIVATE_KEY") {
+                Ok(key) => key,
+                Err(_) => continue,
+            },
+            WalletSource::Mnemonic(index) => {
+                match profile_mnemonic(&profile).and_then(|m| derive(&m, index)) {
+                    Ok(account) => account.private_key,
+                    Err(_) => continue,
+                }
+            }
+            WalletSource::Foundry(_) | WalletSource::Keystore(_) => continue,
+        };
+        if let Err(e) = check_pair(address, &private_key) {
+            let derived = address_of(&private_key)?;
+            if fix {
+                config.set_wallet_address(&name, &derived)?;
+                changed = true;
+                println!(
+                    "{} {}: set wallet-address of [profile.{}] to {}",
+                    CHECK_MARK, CONFIG_FILE, name, derived
+                );
+            } else {
+                println!("{} {} [profile.{}]: {}", CROSS_MARK, CONFIG_FILE, name, e);
+                problems += 1;
+            }
+        }
+    }
+    if changed {
+        config.save()?;
+    }
+
+    if problems > 0 {
+        let hint = if fix {
+            ""
+        } else {
+            ", run with --fix to correct them"
+        };
+        return Err(format!("Found {} problem(s){}", problems, hint));
+    }
+    println!("{} Wallet addresses match their private keys", CHECK_MARK);
+    Ok(())
+}
+
+/// Check the wallet pair of one env file, returning the number of problems left
+fn check_env_file(path: &Path, fix: bool) -> Result<usize, String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let find = |lines: &[String], key: &str| {
+        lines.iter().position(|line| {
+            line.trim()
+                .strip_prefix("export ")
+                .and_then(|a| a.split_once('='))
+                .is_some_and(|(k, _)| k.trim() == key)
+        })
+    };
+    let value = |line: &str| unquote(line.split_once('=').map_or("", |(_, v)| v.trim()));
+
+    let key_line = find(&lines, "ETH_WALLET_PRIVATE_KEY");
+    let address_line = find(&lines, "ETH_WALLET_ADDRESS");
+
+    let (message, fixed) = match (key_line, address_line) {
+        (Some(k), address_line) => {
+            let private_key = value(&lines[k]);
+            let derived = match address_of(&private_key) {
+                Ok(derived) => derived,
+                Err(e) => {
+                    println!("{} {}: {}", CROSS_MARK, name, e);
+                    return Ok(1);
+                }
+            };
+            let fixed = Shell::Bash.assignment("ETH_WALLET_ADDRESS", &derived);
+            match address_line {
+                None => {
+                    if fix {
+                        lines.insert(k, fixed.clone());
+                    }
+                    ("ETH_WALLET_ADDRESS is missing".to_string(), fixed)
+                }
+                Some(a) => match check_pair(&value(&lines[a]), &private_key) {
+                    Ok(_) => return Ok(0),
+                    Err(e) => {
+                        if fix {
+                            lines[a] = fixed.clone();
+                        }
+                        (e, fixed)
+                    }
+                },
+            }
+        }
+        (None, Some(a)) => match parse_address(&value(&lines[a])) {
+            Ok(checksummed) if checksummed == value(&lines[a]) => return Ok(0),
+            Ok(checksummed) => {
+                let fixed = Shell::Bash.assignment("ETH_WALLET_ADDRESS", &checksummed);
+                if fix {
+                    lines[a] = fixed.clone();
+                }
+                ("ETH_WALLET_ADDRESS is not checksummed".to_string(), fixed)
+            }
+            Err(e) => {
+                println!("{} {}: {}", CROSS_MARK, name, e);
+                return Ok(1);
+            }
+        },
+        (None, None) => return Ok(0),
+    };
+
+    if !fix {
+        println!("{} {}: {}", CROSS_MARK, name, message);
+        println!("    fix: {}", fixed);
+        return Ok(1);
+    }
+    write_private(path, &(lines.join("\n") + "\n"))?;
+    println!("{} {}: {} (fixed)", CHECK_MARK, name, message);
+    Ok(0)
+}
+
+/// `berry env`: print the project environment for a shell
+pub fn print_env(
+    dir: Option<&str>,
+    profile: Option<&str>,
+    shell: Option<Shell>,
+) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let shell = shell.unwrap_or_else(Shell::detect);
+
+    let vars = load_vars(dir, profile)?;
+    let mut output = String::new();
+    if shell != Shell::Dotenv {
+        output.push_str(&format!("# Load with: {}\n", shell.usage()));
+    }
+    for (key, value) in &vars {
+        output.push_str(&shell.assignment(key, value));
+        output.push('\n');
+    }
+
+    // The output is meant to be piped, so a closed pipe is not an error
+    let _ = io::stdout().write_all(output.as_bytes());
+    Ok(())
+}
+
+/// `berry run -- <command>`: run a command with the project environment, returning its exit code
+pub fn run_with_env(
+    dir: Option<&str>,
+    profile: Option<&str>,
+    command: &[String],
+) -> Result<i32, String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let (program, args) = command
+        .split_first()
+        .ok_or("No command given, usage: berry run -- <command> [args...]")?;
+
+    let vars = load_vars(dir, profile)?;
+    let status = Command::new(program)
+        .args(args)
+        .envs(vars)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+fn escape_double_quoted(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Undo the quoting of an env file value, as written by [`Shell::assignment`]
+fn unquote(value: &str) -> String {
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return inner.replace("'\\''", "'");
+    }
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut unescaped = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            unescaped.push(if c == '\\' {
+                chars.next().unwrap_or(c)
+            } else {
+                c
+            });
+        }
+        return unescaped;
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unquote_reverses_assignment() {
+        for value in ["http://localhost:8545", "it's", "a \"b\" \\c", ""] {
+            for shell in [Shell::Bash, Shell::Dotenv] {
+                let line = shell.assignment("KEY", value);
+                let (_, quoted) = line.split_once('=').unwrap();
+                assert_eq!(unquote(quoted), value, "{}", line);
+            }
+        }
+        assert_eq!(unquote("0xabc"), "0xabc");
+    }
+}
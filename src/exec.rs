@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use super::{new_spinner, CHECK_MARK, CROSS_MARK};
+use crate::build::{guest_methods, GuestMethod};
+use crate::r0vm;
+
+/// Options for `berry exec`
+pub struct ExecOptions<'a> {
+    pub dir: Option<&'a str>,
+    pub input: &'a str,
+    pub guest: Option<&'a str>,
+    pub release: bool,
+    pub baseline: Option<&'a str>,
+    pub update_baseline: bool,
+    /// Allowed growth over the baseline, in percent
+    pub tolerance: f64,
+}
+
+/// Cost of one execution, as stored per guest in a baseline file
+#[derive(Serialize, Deserialize)]
+struct Cost {
+    segments: u64,
+    total_cycles: u64,
+    user_cycles: u64,
+    journal_bytes: u64,
+}
+
+/// `berry exec`: run a guest in the executor without proving and report its cost
+pub fn run_exec(options: &ExecOptions) -> Result<(), String> {
+    let dir = Path::new(options.dir.unwrap_or("."));
+    let input = Path::new(options.input);
+    if !input.is_file() {
+        return Err(format!("Input file {} not found", input.display()));
+    }
+    let guests = guest_methods(dir, options.release)?;
+    let guest = select_guest(&guests, options.guest)?;
+
+    let pb = new_spinner(format!("Executing {}...", guest.name));
+    let started = Instant::now();
+    let execution = r0vm::execute(&guest.elf, input, None).inspect_err(|_| {
+        pb.finish_with_message(format!("{} {} failed", CROSS_MARK, guest.name));
+    })?;
+    pb.finish_with_message(format!(
+        "{} Executed {} ({:.1}s)",
+        CHECK_MARK,
+        guest.name,
+        started.elapsed().as_secs_f64()
+    ));
+
+    let stats = &execution.stats;
+    let cost = Cost {
+        segments: stats.segments,
+        total_cycles: stats.total_cycles,
+        user_cycles: stats.user_cycles,
+        journal_bytes: execution.journal.len() as u64,
+    };
+    println!();
+    let Some(baseline_path) = options.baseline.filter(|_| !options.update_baseline) else {
+        for (label, value) in metrics(&cost) {
+            println!("  {:<14}{:>14}", label, with_separators(value));
+        }
+        return match options.baseline {
+            Some(path) => save_baseline(path, &guest.name, cost),
+            None => Ok(()),
+        };
+    };
+    let content = fs::read_to_string(baseline_path)
+        .map_err(|e| format!("Failed to read {}: {}", baseline_path, e))?;
+    let baseline: BTreeMap<String, Cost> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", baseline_path, e))?;
+    let expected = baseline.get(&guest.name).ok_or_else(|| {
+        format!(
+            "{} has no entry for {}, record one with --update-baseline",
+            baseline_path, guest.name
+        )
+    })?;
+    println!(
+        "Compared with {} (tolerance {}%):",
+        baseline_path, options.tolerance
+    );
+    let comparisons = compare(&cost, expected, options.tolerance);
+    for comparison in &comparisons {
+        println!(
+            "  {} {:<14}{:>14}  baseline {} ({:+.1}%)",
+            if comparison.regressed {
+                CROSS_MARK
+            } else {
+                CHECK_MARK
+            },
+            comparison.label,
+            with_separators(comparison.value),
+            with_separators(comparison.expected),
+            comparison.change
+        );
+    }
+    let regressions = comparisons.iter().filter(|c| c.regressed).count();
+    if regressions > 0 {
+        return Err(format!(
+            "{} metric(s) of {} grew beyond the baseline, run with --update-baseline if expected",
+            regressions, guest.name
+        ));
+    }
+    Ok(())
+}
+
+/// One metric of an execution next to its baseline value
+struct Comparison {
+    label: &'static str,
+    value: u64,
+    expected: u64,
+    /// Growth over the baseline, in percent
+    change: f64,
+    regressed: bool,
+}
+
+/// Compare each metric with the baseline; a metric regresses when it grew beyond `tolerance`
+fn compare(cost: &Cost, baseline: &Cost, tolerance: f64) -> Vec<Comparison> {
+    metrics(cost)
+        .into_iter()
+        .zip(metrics(baseline))
+        .map(|((label, value), (_, expected))| {
+            let change = if expected == 0 {
+                if value == 0 {
+                    0.0
+                } else {
+                    f64::INFINITY
+                }
+            } else {
+                (value as f64 - expected as f64) * 100.0 / expected as f64
+            };
+            Comparison {
+                label,
+                value,
+                expected,
+                change,
+                regressed: change > tolerance,
+            }
+        })
+        .collect()
+}
+
+/// Store a guest's cost in the baseline file, keeping the other guests' entries
+fn save_baseline(path: &str, guest: &str, cost: Cost) -> Result<(), String> {
+    let mut baseline: BTreeMap<String, Cost> = match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path, e))?,
+        Err(_) => BTreeMap::new(),
+    };
+    baseline.insert(guest.to_string(), cost);
+    let content = serde_json::to_string_pretty(&baseline)
+        .map_err(|e| format!("Failed to serialize the baseline: {}", e))?;
+    fs::write(path, content + "\n").map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    println!("\n{} Saved the {} baseline to {}", CHECK_MARK, guest, path);
+    Ok(())
+}
+
+/// The guest named on the command line, or the only guest of the methods crate
+fn select_guest<'a>(
+    guests: &'a [GuestMethod],
+    name: Option<&str>,
+) -> Result<&'a GuestMethod, String> {
+    let names = || {
+        guests
+            .iter()
+            .map(|g| g.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match name {
+        Some(name) => guests
+            .iter()
+            .find(|g| g.name.eq_ignore_ascii_case(&name.replace('-', "_")))
+            .ok_or_else(|| format!("No guest named {} (available: {})", name, names())),
+        None if guests.len() == 1 => Ok(&guests[0]),
+        None if guests.is_empty() => Err("The methods crate has no guests".to_string()),
+        None => Err(format!(
+            "The methods crate has several guests, pick one with --guest ({})",
+            names()
+        )),
+    }
+}
+
+fn metrics(cost: &Cost) -> [(&'static str, u64); 4] {
+    [
+        ("Segments", cost.segments),
+        ("Total cycles", cost.total_cycles),
+        ("User cycles", cost.user_cycles),
+        ("Journal bytes", cost.journal_bytes),
+    ]
+}
+
+/// `1234567` -> `1,234,567`
+fn with_separators(value: u64) -> String {
+    let digits = value.to_string();
+    let mut result = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASELINE: Cost = Cost {
+        segments: 2,
+        total_cycles: 2_000_000,
+        user_cycles: 1_000_000,
+        journal_bytes: 0,
+    };
+
+    #[test]
+    fn separates_thousands() {
+        assert_eq!(with_separators(0), "0");
+        assert_eq!(with_separators(999), "999");
+        assert_eq!(with_separators(1000), "1,000");
+        assert_eq!(with_separators(1234567), "1,234,567");
+        assert_eq!(with_separators(123456789), "123,456,789");
+    }
+
+    #[test]
+    fn growth_within_tolerance_passes() {
+        let cost = Cost {
+            user_cycles: 1_040_000,
+            ..BASELINE
+        };
+        let comparisons = compare(&cost, &BASELINE, 5.0);
+        assert!(comparisons.iter().all(|c| !c.regressed));
+        assert!((comparisons[2].change - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn growth_beyond_tolerance_regresses() {
+        let cost = Cost {
+            total_cycles: 2_200_000,
+            ..BASELINE
+        };
+        let regressed: Vec<_> = compare(&cost, &BASELINE, 5.0)
+            .into_iter()
+            .filter(|c| c.regressed)
+            .map(|c| c.label)
+            .collect();
+        assert_eq!(regressed, ["Total cycles"]);
+    }
+
+    #[test]
+    fn shrinking_never_regresses() {
+        let cost = Cost {
+            segments: 1,
+            total_cycles: 1_000_000,
+            user_cycles: 500_000,
+            ..BASELINE
+        };
+        assert!(compare(&cost, &BASELINE, 0.0).iter().all(|c| !c.regressed));
+    }
+
+    #[test]
+    fn growth_from_zero_regresses() {
+        let cost = Cost {
+            journal_bytes: 32,
+            ..BASELINE
+        };
+        let comparisons = compare(&cost, &BASELINE, 50.0);
+        assert!(comparisons[3].regressed);
+        assert_eq!(compare(&BASELINE, &BASELINE, 0.0)[3].change, 0.0);
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::path::Path;
+
+use toml_edit::{value, Array, DocumentMut, Item, Table, Value};
+
+use super::{new_spinner, ScaffoldOptions, CHECK_MARK, CROSS_MARK};
+use crate::libs::LibMode;
+use crate::remappings::{rebase_escaping_path, Remapping};
+use crate::report::{find_line, Report};
+use crate::workspace::write_manifest;
+
+/// Profile keys holding a single path
+const PATH_KEYS: &[&str] = &["src", "out", "test", "script", "cache_path", "broadcast"];
+
+/// Update foundry.toml configuration across all profiles
+///
+/// Every rewrite checks the current value first, so running this twice leaves
+/// the file unchanged.
+pub fn update_foundry_config(
+    dir: &str,
+    options: &ScaffoldOptions,
+    report: &mut Report,
+) -> Result<(), String> {
+    let pb = new_spinner("Updating foundry.toml...");
+
+    let foundry_path = Path::new(dir).join("foundry.toml");
+    if !foundry_path.exists() {
+        pb.finish_with_message(format!("{} foundry.toml not found", CROSS_MARK));
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&foundry_path)
+        .map_err(|e| format!("Failed to read foundry.toml: {}", e))?;
+    let mut doc = content
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("Failed to parse foundry.toml: {}", e))?;
+    let mode = options.lib_mode;
+    report.expect(STEP, "libs");
+    let mut changes = Changes {
+        report,
+        content: &content,
+        file: &foundry_path,
+    };
+
+    let profiles = doc
+        .entry("profile")
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+        .ok_or("[profile] in foundry.toml is not a table")?;
+    if !profiles.contains_key("default") {
+        profiles.insert("default", Item::Table(Table::new()));
+    }
+
+    for (name, profile) in profiles.iter_mut() {
+        let profile = profile
+            .as_table_mut()
+            .ok_or_else(|| format!("[profile.{}] in foundry.toml is not a table", name))?;
+        rewrite_profile(profile, mode, &mut changes)?;
+    }
+
+    // Settings that only belong in the default profile
+    let default = profiles["default"]
+        .as_table_mut()
+        .ok_or("[profile.default] in foundry.toml is not a table")?;
+    if !default.contains_key("auto_detect_remappings") {
+        default.insert("auto_detect_remappings", value(false));
+        changes.record("auto_detect_remappings", "", "false");
+    }
+    for (key, version) in [
+        ("solc_version", &options.solc_version),
+        ("evm_version", &options.evm_version),
+    ] {
+        let Some(version) = version else {
+            continue;
+        };
+        let before = default.get(key).and_then(Item::as_str).unwrap_or_default();
+        if before != version {
+            changes.record(key, before, version);
+            default.insert(key, value(version.as_str()));
+        }
+    }
+
+    write_manifest(&foundry_path, &doc)?;
+
+    if report.count(STEP) == 0 {
+        pb.finish_with_message(format!(
+            "{} foundry.toml: no rewrite rules matched",
+            CROSS_MARK
+        ));
+    } else {
+        pb.finish_with_message(format!("{} foundry.toml updated successfully", CHECK_MARK));
+    }
+    Ok(())
+}
+
+/// Step name used in the generation report
+const STEP: &str = "foundry";
+
+/// Records foundry.toml rewrites along with the line they were found on
+struct Changes<'a> {
+    report: &'a mut Report,
+    content: &'a str,
+    file: &'a Path,
+}
+
+impl Changes<'_> {
+    fn record(&mut self, rule: &str, before: &str, after: &str) {
+        let line = Some(before)
+            .filter(|b| !b.is_empty())
+            .and_then(|b| find_line(self.content, &[b]));
+        self.report
+            .record(STEP, rule, self.file, line, before, after);
+    }
+}
+
+/// Rewrite every path in a profile that points outside the project
+fn rewrite_profile(
+    profile: &mut Table,
+    mode: LibMode,
+    changes: &mut Changes,
+) -> Result<(), String> {
+    // Library search paths collapse onto the project's own library directory
+    if let Some(libs) = profile.get_mut("libs").and_then(Item::as_array_mut) {
+        let libs_dir = match mode {
+            LibMode::Soldeer => "dependencies",
+            _ => "lib",
+        };
+        let mut rewritten = Array::new();
+        for entry in libs.iter().filter_map(Value::as_str) {
+            let entry = if escapes(entry) {
+                changes.record("libs", entry, libs_dir);
+                libs_dir
+            } else {
+                entry
+            };
+            if !rewritten.iter().any(|v| v.as_str() == Some(entry)) {
+                rewritten.push(entry);
+            }
+        }
+        if !rewritten.iter().any(|v| v.as_str() == Some(libs_dir)) {
+            rewritten.push(libs_dir);
+        }
+        if !same_strings(libs, &rewritten) {
+            *libs = rewritten;
+        }
+    }
+
+    for key in PATH_KEYS {
+        if let Some(item) = profile.get_mut(key) {
+            if let Some(path) = item.as_str().filter(|p| escapes(p)) {
+                let rebased = rebase_escaping_path(path, mode)?;
+                changes.record(key, path, &rebased);
+                *item = value(rebased);
+            }
+        }
+    }
+
+    if let Some(remappings) = profile.get_mut("remappings").and_then(Item::as_array_mut) {
+        for entry in remappings.iter_mut() {
+            let Some(remapping) = entry.as_str() else {
+                continue;
+            };
+            let mut parsed: Remapping = remapping.parse()?;
+            parsed.target = rebase_escaping_path(&parsed.target, mode)?;
+            if let Some(context) = &parsed.context {
+                parsed.context = Some(rebase_escaping_path(context, mode)?);
+            }
+            let rendered = parsed.to_string();
+            if rendered != remapping {
+                changes.record("remappings", remapping, &rendered);
+                *entry = rendered.into();
+            }
+        }
+    }
+
+    if let Some(paths) = profile.get_mut("allow_paths").and_then(Item::as_array_mut) {
+        for entry in paths.iter_mut() {
+            if let Some(path) = entry.as_str().filter(|p| escapes(p)) {
+                let rebased = rebase_escaping_path(path, mode)?;
+                changes.record("allow_paths", path, &rebased);
+                *entry = rebased.into();
+            }
+        }
+    }
+
+    if let Some(permissions) = profile
+        .get_mut("fs_permissions")
+        .and_then(Item::as_array_mut)
+    {
+        for permission in permissions.iter_mut() {
+            let Some(table) = permission.as_inline_table_mut() else {
+                continue;
+            };
+            if let Some(path) = table.get("path").and_then(Value::as_str) {
+                if escapes(path) {
+                    let rebased = rebase_escaping_path(path, mode)?;
+                    changes.record("fs_permissions", path, &rebased);
+                    table.insert("path", rebased.into());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a project-relative path climbs out of the project
+fn escapes(path: &str) -> bool {
+    path == ".." || path.starts_with("../")
+}
+
+fn same_strings(a: &Array, b: &Array) -> bool {
+    a.iter().map(Value::as_str).eq(b.iter().map(Value::as_str))
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::{CHECK_MARK, CROSS_MARK};
+use crate::build::{guest_methods, GuestMethod};
+use crate::walk::project_files;
+
+/// Solidity library risc0-build generates with the image IDs contracts trust
+const IMAGE_ID_SOL: &str = "ImageID.sol";
+
+/// Deployment records written by `berry deploy`, relative to the project root
+pub const DEPLOYMENTS_DIR: &str = "deployments";
+
+/// `berry image-id`: show the image ID of each guest method and check it against
+/// ImageID.sol and the recorded deployments
+pub fn run_image_id(dir: Option<&str>, release: bool, check: bool) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let guests = guest_methods(dir, release)?;
+    for guest in &guests {
+        println!("{}  {}", guest.name, guest.image_id);
+    }
+
+    println!();
+    let mismatches = report_drift(dir, &guests)?;
+    if check && mismatches > 0 {
+        return Err(format!(
+            "{} image ID mismatch(es) between the build and the contracts",
+            mismatches
+        ));
+    }
+    Ok(())
+}
+
+/// Compare the built image IDs with ImageID.sol and the recorded deployments,
+/// printing each source's status; returns the number of mismatches
+pub fn report_drift(dir: &Path, guests: &[GuestMethod]) -> Result<usize, String> {
+    let built: BTreeMap<&str, String> = guests
+        .iter()
+        .map(|g| (g.name.as_str(), g.image_id.to_lowercase()))
+        .collect();
+    let mut mismatches = 0;
+
+    for path in project_files(dir, IMAGE_ID_SOL)? {
+        let label = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", label, e))?;
+        let recorded = solidity_image_ids(&content);
+        let mut problems = Vec::new();
+        for (name, id) in &built {
+            match recorded.get(*name) {
+                Some(recorded) if recorded == id => {}
+                Some(recorded) => {
+                    problems.push(format!("{}_ID is {}, the build has {}", name, recorded, id))
+                }
+                None => problems.push(format!("{}_ID is missing", name)),
+            }
+        }
+        mismatches += print_status(
+            &label,
+            &problems,
+            "rebuild the guest to regenerate it, then rebuild the contracts",
+        );
+    }
+
+    let Ok(entries) = fs::read_dir(dir.join(DEPLOYMENTS_DIR)) else {
+        return Ok(mismatches);
+    };
+    let mut deployments: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    deployments.sort();
+    for path in deployments {
+        let label = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", label, e))?;
+        let record: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", label, e))?;
+        let Some(deployed) = record["image_ids"].as_object() else {
+            continue;
+        };
+        let problems: Vec<String> = deployed
+            .iter()
+            .filter_map(|(name, id)| {
+                let id = id.as_str()?.to_lowercase();
+                let current = built.get(name.as_str())?;
+                (*current != id).then(|| {
+                    format!(
+                        "{} was deployed with {}, the build has {}",
+                        name, id, current
+                    )
+                })
+            })
+            .collect();
+        mismatches += print_status(
+            &label,
+            &problems,
+            "proofs from this build will fail verification until you redeploy",
+        );
+    }
+    Ok(mismatches)
+}
+
+/// Image IDs the contracts are compiled with, read from the project's ImageID.sol
+pub fn contract_image_ids(dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut ids = BTreeMap::new();
+    for path in project_files(dir, IMAGE_ID_SOL)? {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        ids.extend(solidity_image_ids(&content));
+    }
+    Ok(ids)
+}
+
+fn print_status(label: &str, problems: &[String], hint: &str) -> usize {
+    if problems.is_empty() {
+        println!("{} {} matches the build", CHECK_MARK, label);
+        return 0;
+    }
+    for problem in problems {
+        println!("{} {}: {}", CROSS_MARK, label, problem);
+    }
+    println!("  {}", hint);
+    problems.len()
+}
+
+/// Read `bytes32 public constant NAME_ID = bytes32(0x...);` declarations, which
+/// formatters may wrap over several lines
+fn solidity_image_ids(content: &str) -> BTreeMap<String, String> {
+    content
+        .split(';')
+        .filter_map(|statement| {
+            let (_, declaration) = statement.split_once("constant ")?;
+            let (name, value) = declaration.split_once('=')?;
+            let name = name.trim().strip_suffix("_ID")?;
+            let hex_start = value.find("0x")?;
+            let id: String = value[hex_start + 2..]
+                .chars()
+                .take_while(char::is_ascii_hexdigit)
+                .collect();
+            Some((name.to_string(), format!("0x{}", id.to_lowercase())))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_image_ids_from_image_id_sol() {
+        let content = r#"// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.20;
+
+library ImageID {
+    bytes32 public constant BALANCE_OF_ID =
+        bytes32(0x2C4B6A1F0E9D8C7B6A5F4E3D2C1B0A99887766554433221100FFEEDDCCBBAA99);
+    bytes32 public constant IS_EVEN_ID = bytes32(0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef);
+}
+"#;
+        let ids = solidity_image_ids(content);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(
+            ids["BALANCE_OF"],
+            "0x2c4b6a1f0e9d8c7b6a5f4e3d2c1b0a99887766554433221100ffeeddccbbaa99"
+        );
+        assert_eq!(
+            ids["IS_EVEN"],
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+        );
+    }
+
+    #[test]
+    fn ignores_constants_that_are_not_image_ids() {
+        let content = "library ImageID {\n    uint256 public constant VERSION = 1;\n}\n";
+        assert!(solidity_image_ids(content).is_empty());
+    }
+}
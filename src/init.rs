@@ -0,0 +1,193 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{
+    clone_repository, new_spinner, run_git_command, setup_project_files, setup_sparse_checkout,
+    update_cargo_dependencies, ScaffoldOptions, CHECK_MARK,
+};
+use crate::report::Report;
+use crate::{foundry, libs, remappings, workspace};
+
+/// Scratch directory the template is prepared in before being merged
+const STAGING_DIR: &str = ".berry-template";
+
+/// Scaffold a project into the current directory or an existing subdirectory
+pub fn init_existing(subdir: Option<&str>, options: &ScaffoldOptions) -> Result<(), String> {
+    let mut target = PathBuf::from(".");
+    if let Some(subdir) = subdir {
+        crate::validate::validate_subdir(subdir)?;
+        target = target.join(subdir);
+        fs::create_dir_all(&target)
+            .map_err(|e| format!("Failed to create directory '{}': {}", subdir, e))?;
+    }
+    let target_str = path_str(&target)?;
+
+    let staging = target.join(STAGING_DIR);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)
+            .map_err(|e| format!("Failed to remove leftover {}: {}", staging.display(), e))?;
+    }
+
+    let mut report = Report::new(&staging);
+    let result = prepare_template(&staging, options, &mut report)
+        .and_then(|_| merge_template(&staging, &target));
+    if staging.exists() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result?;
+    report.set_root(&target);
+
+    // Install Solidity libraries without touching an existing .git
+    libs::install_libraries(target_str, options.lib_mode, &options.revs, true)?;
+
+    // Update remappings.txt
+    remappings::update_remappings(target_str, options.lib_mode, &mut report)?;
+
+    // Join or step out of an enclosing cargo workspace
+    workspace::configure_workspace(&target, options.workspace)?;
+
+    // Write the generation report and flag rules that matched nothing
+    report.finish(&target)?;
+
+    // Verify the rewrite steps left a consistent project
+    super::report_project_check(target_str);
+
+    let location = subdir.unwrap_or(".");
+    println!("\n🫐 Project initialized in {} successfully!", location);
+    println!("\nNext steps:");
+    println!("1. berry setup {}", location);
+    println!("2. cd {}", location);
+    println!("3. source env.sh");
+    println!("4. berry e2e  # dev-mode proving on a local node, no Bonsai API key needed");
+    println!(
+        "For real proofs use `berry setup --prover local`, or `--prover bonsai` with BONSAI_API_KEY set"
+    );
+    Ok(())
+}
+
+/// Clone and rewrite the template in the staging directory
+fn prepare_template(
+    staging: &Path,
+    options: &ScaffoldOptions,
+    report: &mut Report,
+) -> Result<(), String> {
+    let staging_str = path_str(staging)?;
+
+    clone_repository(staging_str, "release-1.3").map_err(|e| e.to_string())?;
+    run_git_command(staging_str, &["checkout", "release-1.3"])?;
+    setup_sparse_checkout(staging_str)?;
+    setup_project_files(staging_str)?;
+    update_cargo_dependencies(staging_str, &options.revs, report)?;
+    foundry::update_foundry_config(staging_str, options, report)?;
+
+    // The template's own history is not part of the scaffolded project
+    fs::remove_dir_all(staging.join(".git"))
+        .map_err(|e| format!("Failed to remove template .git directory: {}", e))?;
+    let lib_path = staging.join("lib");
+    if lib_path.exists() {
+        fs::remove_dir_all(&lib_path)
+            .map_err(|e| format!("Failed to remove template lib directory: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Move the staged template into the target, refusing to overwrite anything
+fn merge_template(staging: &Path, target: &Path) -> Result<(), String> {
+    let pb = new_spinner("Merging project files...");
+
+    let mut files = Vec::new();
+    collect_files(staging, Path::new(""), &mut files)?;
+
+    // Find files that exist in the target with different content
+    let mut conflicts = Vec::new();
+    for rel in &files {
+        if rel == Path::new(".gitignore") {
+            continue;
+        }
+        let existing = target.join(rel);
+        if existing.exists() {
+            let same =
+                existing.is_file() && fs::read(&existing).ok() == fs::read(staging.join(rel)).ok();
+            if !same {
+                conflicts.push(rel.display().to_string());
+            }
+        }
+    }
+    if !conflicts.is_empty() {
+        pb.abandon();
+        return Err(format!(
+            "The following files already exist and would be overwritten:\n  {}\nMove or rename them, or use `berry init --subdir <dir>` to scaffold into a separate directory.",
+            conflicts.join("\n  ")
+        ));
+    }
+
+    for rel in &files {
+        let from = staging.join(rel);
+        let to = target.join(rel);
+        if rel == Path::new(".gitignore") {
+            merge_gitignore(&from, &to)?;
+            continue;
+        }
+        if to.exists() {
+            continue;
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::rename(&from, &to).map_err(|e| format!("Failed to move {}: {}", rel.display(), e))?;
+    }
+
+    pb.finish_with_message(format!("{} Project files merged successfully", CHECK_MARK));
+    Ok(())
+}
+
+/// Append template .gitignore entries missing from the existing one
+fn merge_gitignore(from: &Path, to: &Path) -> Result<(), String> {
+    let template =
+        fs::read_to_string(from).map_err(|e| format!("Failed to read .gitignore: {}", e))?;
+    if !to.exists() {
+        return fs::write(to, template).map_err(|e| format!("Failed to write .gitignore: {}", e));
+    }
+
+    let mut existing =
+        fs::read_to_string(to).map_err(|e| format!("Failed to read .gitignore: {}", e))?;
+    let present: Vec<String> = existing.lines().map(|l| l.trim().to_string()).collect();
+    let missing: Vec<&str> = template
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !present.iter().any(|p| p == l))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        existing.push('\n');
+    }
+    existing.push_str("\n# Added by berry\n");
+    for line in missing {
+        existing.push_str(line);
+        existing.push('\n');
+    }
+    fs::write(to, existing).map_err(|e| format!("Failed to write .gitignore: {}", e))
+}
+
+/// Recursively collect file paths relative to `root`
+fn collect_files(root: &Path, rel: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in fs::read_dir(root.join(rel)).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let rel_path = rel.join(entry.file_name());
+        if entry.path().is_dir() {
+            collect_files(root, &rel_path, files)?;
+        } else {
+            files.push(rel_path);
+        }
+    }
+    Ok(())
+}
+
+fn path_str(path: &Path) -> Result<&str, String> {
+    path.to_str()
+        .ok_or_else(|| format!("Path {} is not valid UTF-8", path.display()))
+}
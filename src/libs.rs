@@ -0,0 +1,525 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use toml_edit::{value, InlineTable, Item, Table};
+
+use super::{new_spinner, run_git_command, CHECK_MARK};
+use crate::lock;
+use crate::workspace::{read_manifest, write_manifest};
+
+/// How the Solidity libraries are brought into the project
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LibMode {
+    /// Git submodules under lib/
+    #[default]
+    Submodule,
+    /// Plain copies under lib/, with the source commit recorded
+    Vendor,
+    /// `forge install --no-git` into lib/
+    ForgeInstall,
+    /// Soldeer dependencies declared in foundry.toml
+    Soldeer,
+}
+
+/// File recording where a vendored copy under lib/ came from
+pub const VCS_INFO_FILE: &str = ".berry_vcs_info.json";
+
+/// Contents of [`VCS_INFO_FILE`]
+#[derive(Serialize, Deserialize)]
+pub struct VcsInfo {
+    pub git: GitSource,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitSource {
+    pub url: String,
+    pub rev: String,
+    pub sha1: String,
+}
+
+impl VcsInfo {
+    /// Read the source recorded in a vendored library, if it has one
+    pub fn read(lib_dir: &Path) -> Option<VcsInfo> {
+        let content = fs::read_to_string(lib_dir.join(VCS_INFO_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+}
+
+/// A Solidity library the template depends on
+pub struct Library {
+    /// Directory name under lib/
+    pub name: &'static str,
+    /// Human readable name for progress messages
+    pub label: &'static str,
+    pub url: &'static str,
+    /// Soldeer registry name
+    pub soldeer_name: &'static str,
+    /// Soldeer version, also part of the install directory name
+    pub soldeer_version: &'static str,
+    /// Whether Soldeer must fetch the library from git rather than its registry
+    pub soldeer_git: bool,
+}
+
+pub const LIBRARIES: &[Library] = &[
+    Library {
+        name: "forge-std",
+        label: "forge-std",
+        url: "https://github.com/foundry-rs/forge-std",
+        soldeer_name: "forge-std",
+        soldeer_version: "1.9.4",
+        soldeer_git: false,
+    },
+    Library {
+        name: "openzeppelin-contracts",
+        label: "OpenZeppelin contracts",
+        url: "https://github.com/OpenZeppelin/openzeppelin-contracts",
+        soldeer_name: "@openzeppelin-contracts",
+        soldeer_version: "5.1.0",
+        soldeer_git: false,
+    },
+    Library {
+        name: "risc0-ethereum",
+        label: "RISC0 ethereum",
+        url: "https://github.com/risc0/risc0-ethereum",
+        soldeer_name: "risc0-ethereum",
+        soldeer_version: "1.3",
+        soldeer_git: true,
+    },
+];
+
+/// The risc0-ethereum release the template is taken from
+pub const RISC0_ETHEREUM_RELEASE: &str = "release-1.3";
+
+/// Default library revisions for each supported risc0-ethereum release
+const DEFAULT_REVISIONS: &[(&str, &[(&str, &str)])] = &[(
+    "release-1.3",
+    &[
+        ("forge-std", "v1.9.4"),
+        ("openzeppelin-contracts", "v5.1.0"),
+        ("risc0-ethereum", "v1.3.0"),
+    ],
+)];
+
+/// The revision each library is pinned to
+pub struct Revisions {
+    revs: HashMap<&'static str, String>,
+}
+
+impl Revisions {
+    /// Start from the defaults for a release and apply `name=rev` overrides
+    ///
+    /// Soldeer installs into directories named after the registry versions berry
+    /// pins, so it takes no overrides.
+    pub fn resolve(release: &str, mode: LibMode, overrides: &[String]) -> Result<Self, String> {
+        if mode == LibMode::Soldeer && !overrides.is_empty() {
+            return Err(
+                "--lib-rev cannot be used with --lib-mode soldeer, which installs the registry versions berry pins; use --lib-mode submodule, vendor or forge-install to pin a revision"
+                    .to_string(),
+            );
+        }
+
+        let defaults = DEFAULT_REVISIONS
+            .iter()
+            .find(|(r, _)| *r == release)
+            .map(|(_, revs)| *revs)
+            .ok_or_else(|| format!("No default library revisions for {}", release))?;
+
+        let mut revs = HashMap::new();
+        for (name, rev) in defaults {
+            revs.insert(Library::get(name).name, rev.to_string());
+        }
+
+        for entry in overrides {
+            let (name, rev) = entry
+                .split_once('=')
+                .filter(|(name, rev)| !name.is_empty() && !rev.is_empty())
+                .ok_or_else(|| {
+                    format!("Invalid --lib-rev '{}', expected <library>=<rev>", entry)
+                })?;
+            let lib = LIBRARIES
+                .iter()
+                .find(|lib| lib.name == name)
+                .ok_or_else(|| {
+                    let known: Vec<&str> = LIBRARIES.iter().map(|lib| lib.name).collect();
+                    format!(
+                        "Unknown library '{}' in --lib-rev. Known libraries: {}",
+                        name,
+                        known.join(", ")
+                    )
+                })?;
+            revs.insert(lib.name, rev.to_string());
+        }
+
+        Ok(Self { revs })
+    }
+
+    /// The revisions berry pins for its risc0-ethereum release
+    pub fn pinned() -> Self {
+        Self::resolve(RISC0_ETHEREUM_RELEASE, LibMode::default(), &[])
+            .expect("default revisions exist for the pinned release")
+    }
+
+    /// The pinned revision of a library
+    pub fn get(&self, lib: &Library) -> &str {
+        &self.revs[lib.name]
+    }
+
+    /// Cargo source of the risc0-ethereum crates, pinned to the same revision as the library
+    pub fn cargo_source(&self) -> String {
+        let lib = Library::get("risc0-ethereum");
+        let rev = self.get(lib);
+        format!(
+            "git = \"{}\", {} = \"{}\"",
+            lib.url,
+            cargo_ref_key(rev),
+            rev
+        )
+    }
+}
+
+/// Crates the template takes from risc0-ethereum
+pub const RISC0_CRATES: &[&str] = &[
+    "risc0-build-ethereum",
+    "risc0-ethereum-contracts",
+    "risc0-steel",
+];
+
+/// Whether a revision is a full commit hash
+pub fn is_commit(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The Cargo git dependency key for a revision: `rev`, `tag` or `branch`
+pub fn cargo_ref_key(rev: &str) -> &'static str {
+    if is_commit(rev) {
+        "rev"
+    } else if rev
+        .trim_start_matches('v')
+        .starts_with(|c: char| c.is_ascii_digit())
+    {
+        "tag"
+    } else {
+        "branch"
+    }
+}
+
+impl Library {
+    /// Look up a library by its lib/ directory name
+    pub fn get(name: &str) -> &'static Library {
+        LIBRARIES
+            .iter()
+            .find(|lib| lib.name == name)
+            .expect("unknown library")
+    }
+
+    /// Install location relative to the project root
+    pub fn root(&self, mode: LibMode) -> String {
+        match mode {
+            LibMode::Soldeer => format!(
+                "dependencies/{}-{}",
+                self.soldeer_name, self.soldeer_version
+            ),
+            _ => format!("lib/{}", self.name),
+        }
+    }
+
+    /// The `owner/repo` part of the GitHub URL
+    fn github_slug(&self) -> &str {
+        self.url.trim_start_matches("https://github.com/")
+    }
+}
+
+/// Install the Solidity libraries using the selected mode
+///
+/// With `existing_repo` set, the surrounding git repository and any libraries
+/// already present are kept instead of being recreated.
+pub fn install_libraries(
+    dir: &str,
+    mode: LibMode,
+    revs: &Revisions,
+    existing_repo: bool,
+) -> Result<(), String> {
+    match mode {
+        LibMode::Submodule => setup_git_submodules(dir, revs, existing_repo)?,
+        LibMode::Vendor => {
+            prepare_repository(dir, existing_repo)?;
+            vendor_libraries(dir, revs)?;
+        }
+        LibMode::ForgeInstall => {
+            prepare_repository(dir, existing_repo)?;
+            forge_install_libraries(dir, revs)?;
+        }
+        LibMode::Soldeer => {
+            prepare_repository(dir, existing_repo)?;
+            soldeer_libraries(dir, revs)?;
+        }
+    }
+
+    lock::write_lock(dir, mode, revs)
+}
+
+/// Set up Git submodules
+fn setup_git_submodules(dir: &str, revs: &Revisions, existing_repo: bool) -> Result<(), String> {
+    let pb = new_spinner("Setting up Git submodules...");
+
+    let lib_path = PathBuf::from(dir).join("lib");
+    if existing_repo {
+        fs::create_dir_all(&lib_path)
+            .map_err(|e| format!("Failed to create lib directory: {}", e))?;
+        if !is_git_work_tree(dir) {
+            run_git_command(dir, &["init"])?;
+        }
+    } else {
+        // Clean up existing lib directory
+        if lib_path.exists() {
+            fs::remove_dir_all(&lib_path)
+                .map_err(|e| format!("Failed to remove lib directory: {}", e))?;
+        }
+        fs::create_dir_all(&lib_path)
+            .map_err(|e| format!("Failed to create lib directory: {}", e))?;
+
+        // Remove existing .git directory to start fresh
+        let git_path = PathBuf::from(dir).join(".git");
+        if git_path.exists() {
+            fs::remove_dir_all(&git_path)
+                .map_err(|e| format!("Failed to remove .git directory: {}", e))?;
+        }
+
+        // Initialize new git repository
+        run_git_command(dir, &["init"])?;
+    }
+
+    // Initialize and add submodules
+    run_git_command(dir, &["submodule", "init"])?;
+
+    for lib in LIBRARIES {
+        let path = lib.root(LibMode::Submodule);
+        if PathBuf::from(dir).join(&path).exists() {
+            pb.println(format!("  {} already exists, skipping", path));
+            continue;
+        }
+        pb.set_message(format!("Adding {} submodule...", lib.label));
+        run_git_command(dir, &["submodule", "add", lib.url, &path])?;
+
+        // Check out the pinned revision and record it as the submodule commit
+        let rev = revs.get(lib);
+        pb.set_message(format!("Checking out {} at {}...", lib.label, rev));
+        let submodule_dir = format!("{}/{}", dir, path);
+        run_git_command(&submodule_dir, &["fetch", "--quiet", "origin", rev])?;
+        run_git_command(&submodule_dir, &["checkout", "--quiet", "FETCH_HEAD"])?;
+        run_git_command(dir, &["add", &path])?;
+    }
+
+    // Update all submodules recursively
+    pb.set_message("Updating submodules...");
+    run_git_command(
+        dir,
+        &["submodule", "update", "--init", "--recursive", "--quiet"],
+    )?;
+
+    // .gitmodules and the gitlinks stay staged, as `git submodule add` leaves them
+
+    pb.finish_with_message(format!("{} Git submodules set up successfully", CHECK_MARK));
+    Ok(())
+}
+
+/// Replace the template's git history, without nesting a repository in an existing one
+fn prepare_repository(dir: &str, existing_repo: bool) -> Result<(), String> {
+    if existing_repo {
+        return Ok(());
+    }
+
+    let git_path = PathBuf::from(dir).join(".git");
+    if git_path.exists() {
+        fs::remove_dir_all(&git_path)
+            .map_err(|e| format!("Failed to remove .git directory: {}", e))?;
+    }
+    if !is_git_work_tree(dir) {
+        run_git_command(dir, &["init"])?;
+    }
+    Ok(())
+}
+
+/// Copy each library into lib/ as plain files, recording the source commit
+fn vendor_libraries(dir: &str, revs: &Revisions) -> Result<(), String> {
+    let pb = new_spinner("Vendoring libraries...");
+
+    let lib_path = PathBuf::from(dir).join("lib");
+    fs::create_dir_all(&lib_path).map_err(|e| format!("Failed to create lib directory: {}", e))?;
+
+    for lib in LIBRARIES {
+        let target = lib_path.join(lib.name);
+        if target.exists() {
+            pb.println(format!("  lib/{} already exists, skipping", lib.name));
+            continue;
+        }
+        pb.set_message(format!("Vendoring {}...", lib.label));
+
+        // Fetch exactly the pinned revision, which may be a tag, branch or commit
+        let rev = revs.get(lib);
+        fs::create_dir_all(&target)
+            .map_err(|e| format!("Failed to create lib/{}: {}", lib.name, e))?;
+        let target_str = target.to_string_lossy();
+        run_git_command(&target_str, &["init", "--quiet"])?;
+        run_git_command(&target_str, &["remote", "add", "origin", lib.url])?;
+        run_git_command(
+            &target_str,
+            &["fetch", "--quiet", "--depth", "1", "origin", rev],
+        )?;
+        run_git_command(&target_str, &["checkout", "--quiet", "FETCH_HEAD"])?;
+        run_git_command(
+            &target_str,
+            &[
+                "submodule",
+                "update",
+                "--quiet",
+                "--init",
+                "--recursive",
+                "--depth",
+                "1",
+            ],
+        )?;
+
+        let sha = git_output(&target, &["rev-parse", "HEAD"])?;
+        remove_git_metadata(&target)?;
+
+        let info = VcsInfo {
+            git: GitSource {
+                url: lib.url.to_string(),
+                rev: rev.to_string(),
+                sha1: sha,
+            },
+        };
+        let info = serde_json::to_string_pretty(&info)
+            .map_err(|e| format!("Failed to serialize the source of {}: {}", lib.name, e))?;
+        fs::write(target.join(VCS_INFO_FILE), info + "\n")
+            .map_err(|e| format!("Failed to record source of {}: {}", lib.name, e))?;
+    }
+
+    pb.finish_with_message(format!("{} Libraries vendored successfully", CHECK_MARK));
+    Ok(())
+}
+
+/// Install each library with `forge install --no-git`
+fn forge_install_libraries(dir: &str, revs: &Revisions) -> Result<(), String> {
+    let pb = new_spinner("Installing libraries with forge...");
+
+    for lib in LIBRARIES {
+        if PathBuf::from(dir)
+            .join(lib.root(LibMode::ForgeInstall))
+            .exists()
+        {
+            pb.println(format!("  lib/{} already exists, skipping", lib.name));
+            continue;
+        }
+        pb.set_message(format!("Installing {}...", lib.label));
+
+        let spec = format!("{}@{}", lib.github_slug(), revs.get(lib));
+        run_forge_command(dir, &["install", "--no-git", &spec])?;
+    }
+
+    pb.finish_with_message(format!("{} Libraries installed successfully", CHECK_MARK));
+    Ok(())
+}
+
+/// Declare the libraries as Soldeer dependencies in foundry.toml and install them
+fn soldeer_libraries(dir: &str, revs: &Revisions) -> Result<(), String> {
+    let pb = new_spinner("Declaring Soldeer dependencies...");
+
+    let foundry_path = Path::new(dir).join("foundry.toml");
+    let mut doc = read_manifest(&foundry_path)?;
+
+    let deps = doc
+        .entry("dependencies")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or("[dependencies] in foundry.toml is not a table")?;
+    for lib in LIBRARIES {
+        // Registry versions are already pinned; anything else is fetched from git
+        let spec = if lib.soldeer_git {
+            let mut table = InlineTable::new();
+            table.insert("version", lib.soldeer_version.into());
+            table.insert("git", format!("{}.git", lib.url).into());
+            table.insert("rev", revs.get(lib).into());
+            value(table)
+        } else {
+            value(lib.soldeer_version)
+        };
+        deps.insert(lib.soldeer_name, spec);
+    }
+
+    // berry manages remappings itself
+    let soldeer = doc
+        .entry("soldeer")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or("[soldeer] in foundry.toml is not a table")?;
+    soldeer.insert("remappings_generate", value(false));
+    write_manifest(&foundry_path, &doc)?;
+
+    pb.set_message("Installing Soldeer dependencies...");
+    run_forge_command(dir, &["soldeer", "install"])?;
+
+    pb.finish_with_message(format!("{} Soldeer dependencies installed", CHECK_MARK));
+    Ok(())
+}
+
+/// Check whether a directory is inside a git work tree
+pub fn is_git_work_tree(dir: &str) -> bool {
+    Command::new("git")
+        .current_dir(dir)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Run a git command and return its trimmed stdout
+pub fn git_output(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Run a forge command in the specified directory
+fn run_forge_command(dir: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new("forge")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run forge: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(())
+}
+
+/// Remove every `.git` directory or file, and `.gitmodules`, below a vendored library
+fn remove_git_metadata(dir: &Path) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        if entry.file_name() == ".git" || entry.file_name() == ".gitmodules" {
+            let result = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            result.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        } else if path.is_dir() {
+            remove_git_metadata(&path)?;
+        }
+    }
+    Ok(())
+}
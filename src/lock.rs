@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+use toml_edit::{value, ArrayOfTables, DocumentMut, Item, Table};
+
+use crate::libs::{
+    cargo_ref_key, git_output, is_commit, LibMode, Library, Revisions, VcsInfo, LIBRARIES,
+    RISC0_CRATES, RISC0_ETHEREUM_RELEASE,
+};
+
+/// Name of the lockfile written to the project root
+pub const LOCK_FILE: &str = "berry.lock";
+
+/// Record the exact library revisions in the project lockfile
+pub fn write_lock(dir: &str, mode: LibMode, revs: &Revisions) -> Result<(), String> {
+    let mut doc = DocumentMut::new();
+    doc.decor_mut().set_prefix(
+        "# This file is generated by berry and records the exact Solidity library and\n# risc0-ethereum crate revisions.\n# It is not intended for manual editing.\n\n",
+    );
+    doc.insert("version", value(1));
+    doc.insert("risc0-ethereum-release", value(RISC0_ETHEREUM_RELEASE));
+    doc.insert("lib-mode", value(mode_name(mode)));
+
+    let mut libraries = ArrayOfTables::new();
+    let mut risc0_commit = None;
+    for lib in LIBRARIES {
+        let path = lib.root(mode);
+        let rev = revs.get(lib);
+
+        let mut entry = Table::new();
+        entry.insert("name", value(lib.name));
+        entry.insert("source", value(lib.url));
+        entry.insert("rev", value(rev));
+        let commit = resolve_commit(&Path::new(dir).join(&path), lib.url, rev);
+        if let Some(commit) = &commit {
+            entry.insert("commit", value(commit));
+        }
+        if lib.name == "risc0-ethereum" {
+            risc0_commit = commit;
+        }
+        entry.insert("path", value(path));
+        libraries.push(entry);
+    }
+    doc.insert("library", Item::ArrayOfTables(libraries));
+
+    // The risc0-ethereum crates follow the same revision as the Solidity library
+    let risc0 = Library::get("risc0-ethereum");
+    let rev = revs.get(risc0);
+    let mut crates = ArrayOfTables::new();
+    for name in RISC0_CRATES {
+        let mut entry = Table::new();
+        entry.insert("name", value(*name));
+        entry.insert("source", value(risc0.url));
+        entry.insert(cargo_ref_key(rev), value(rev));
+        if let Some(commit) = &risc0_commit {
+            entry.insert("commit", value(commit));
+        }
+        crates.push(entry);
+    }
+    doc.insert("crate", Item::ArrayOfTables(crates));
+
+    let lock_path = Path::new(dir).join(LOCK_FILE);
+    fs::write(&lock_path, doc.to_string())
+        .map_err(|e| format!("Failed to write {}: {}", LOCK_FILE, e))
+}
+
+/// The command line name of a library mode
+pub fn mode_name(mode: LibMode) -> String {
+    mode.to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// The commit checked out or vendored in a library directory, if it records one
+pub fn installed_commit(lib_dir: &Path) -> Option<String> {
+    // Submodules keep their git metadata
+    if lib_dir.join(".git").exists() {
+        return git_output(lib_dir, &["rev-parse", "HEAD"]).ok();
+    }
+
+    // Vendored copies record their source
+    VcsInfo::read(lib_dir).map(|info| info.git.sha1)
+}
+
+/// Find the commit a library was installed at
+fn resolve_commit(lib_dir: &Path, url: &str, rev: &str) -> Option<String> {
+    if is_commit(rev) {
+        return Some(rev.to_lowercase());
+    }
+
+    if let Some(commit) = installed_commit(lib_dir) {
+        return Some(commit);
+    }
+
+    // Otherwise ask the remote, preferring the peeled commit of annotated tags
+    let listing = git_output(Path::new("."), &["ls-remote", url, rev]).ok()?;
+    let mut commit = None;
+    for line in listing.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(sha), Some(reference)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if reference.ends_with("^{}") {
+            return Some(sha.to_string());
+        }
+        commit.get_or_insert_with(|| sha.to_string());
+    }
+    commit
+}
@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use git2::Repository;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::env;
@@ -8,6 +8,36 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Duration;
 
+mod accounts;
+mod address;
+mod bonsai_mock;
+mod build;
+mod check;
+mod deploy;
+mod e2e;
+mod environment;
+mod exec;
+mod foundry;
+mod image_id;
+mod init;
+mod libs;
+mod lock;
+mod node;
+mod profile;
+mod r0vm;
+mod receipt;
+mod remappings;
+mod report;
+mod rpc;
+mod secrets;
+mod validate;
+mod walk;
+mod workspace;
+
+use libs::{LibMode, Revisions};
+use report::Report;
+use workspace::WorkspaceMode;
+
 const ASCII_ART: &str = r#"
     ____                        
    / __ )___  ____________  __
@@ -33,18 +63,334 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Create a new folder
+    /// Create a new project
     New {
-        /// Name of the folder to create
+        /// Name of the project (also the default directory name)
         name: String,
+        /// Directory to create the project in (defaults to the project name)
+        #[arg(long)]
+        path: Option<String>,
+        #[command(flatten)]
+        scaffold: ScaffoldArgs,
+    },
+    /// Scaffold a project into the current directory, keeping existing files
+    Init {
+        /// Scaffold into this subdirectory instead of the current directory
+        #[arg(long)]
+        subdir: Option<String>,
+        #[command(flatten)]
+        scaffold: ScaffoldArgs,
+    },
+    /// Check a generated project for paths escaping it and inconsistent libraries
+    Check {
+        /// Optional project directory (defaults to current directory)
+        dir: Option<String>,
     },
     /// Prepare environment for running end-to-end tests
     Setup {
         /// Optional project directory (defaults to current directory)
         dir: Option<String>,
+        /// Network profile from berry.toml (defaults to the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Also write a non-dev private key into the env file in cleartext
+        #[arg(long)]
+        write_key: bool,
+        /// How to prove the guest; saved to the profile in berry.toml
+        #[arg(long, value_enum)]
+        prover: Option<profile::Prover>,
+    },
+    /// Build the guest, host and contracts, skipping parts whose inputs are unchanged
+    Build {
+        /// Build the guest methods
+        #[arg(long)]
+        guest: bool,
+        /// Build the host workspace
+        #[arg(long)]
+        host: bool,
+        /// Build the contracts with forge
+        #[arg(long)]
+        contracts: bool,
+        /// Build Rust targets with the release profile
+        #[arg(long)]
+        release: bool,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long)]
+        dir: Option<String>,
+    },
+    /// Show the image ID of each guest method and check the contracts trust it
+    ImageId {
+        /// Read the release build instead of the debug build
+        #[arg(long)]
+        release: bool,
+        /// Fail when ImageID.sol or a deployment does not match the build
+        #[arg(long)]
+        check: bool,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long)]
+        dir: Option<String>,
+    },
+    /// Print the project environment, or manage the network profiles
+    Env {
+        #[command(subcommand)]
+        command: Option<EnvCommand>,
+        /// Output syntax (defaults to the shell in $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<environment::Shell>,
+        /// Profile to print (defaults to the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long, global = true)]
+        dir: Option<String>,
+    },
+    /// List and derive development accounts from a mnemonic
+    Accounts {
+        #[command(subcommand)]
+        command: AccountsCommand,
+    },
+    /// Run a local anvil node for the active profile
+    Node {
+        #[command(subcommand)]
+        command: NodeCommand,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long, global = true)]
+        dir: Option<String>,
+    },
+    /// Run a guest in the executor without proving and report its cycle counts
+    Exec {
+        /// File with the bytes the guest reads from its input
+        #[arg(long)]
+        input: String,
+        /// Guest to run, when the methods crate has several
+        #[arg(long)]
+        guest: Option<String>,
+        /// Run the release build instead of the debug build
+        #[arg(long)]
+        release: bool,
+        /// JSON file with the expected cycle counts per guest
+        #[arg(long)]
+        baseline: Option<String>,
+        /// Save the counts to the baseline file instead of comparing against it
+        #[arg(long, requires = "baseline")]
+        update_baseline: bool,
+        /// Growth over the baseline, in percent, that still passes
+        #[arg(long, default_value_t = 0.0)]
+        tolerance: f64,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long)]
+        dir: Option<String>,
+    },
+    /// Inspect, verify or convert a bincode receipt
+    Receipt {
+        #[command(subcommand)]
+        command: ReceiptCommand,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long, global = true)]
+        dir: Option<String>,
+    },
+    /// Deploy the contracts and record them in deployments/<chain-id>.json
+    Deploy {
+        /// Profile to deploy with (defaults to the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long)]
+        dir: Option<String>,
+    },
+    /// Deploy, publish and check the result end to end
+    E2e {
+        /// Profile to test against (defaults to the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Where to write the JUnit XML report (defaults to .berry/e2e-junit.xml)
+        #[arg(long)]
+        junit: Option<String>,
+        /// Leave a node started for the test running afterwards
+        #[arg(long)]
+        keep_node: bool,
+        /// Account whose token balance is proven (defaults to the profile's user account,
+        /// then the wallet)
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Serve the Bonsai REST API locally, proving with dev-mode fakes
+    BonsaiMock {
+        /// Port to listen on
+        #[arg(long, default_value_t = bonsai_mock::BONSAI_MOCK_PORT)]
+        port: u16,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long)]
+        dir: Option<String>,
+    },
+    /// Manage encrypted wallet keystores used by profiles
+    Keystore {
+        #[command(subcommand)]
+        command: KeystoreCommand,
+    },
+    /// Run a command with the project environment loaded
+    Run {
+        /// Profile to load (defaults to the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Optional project directory (defaults to current directory)
+        #[arg(long)]
+        dir: Option<String>,
+        /// Command to run, after `--`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
     },
 }
 
+#[derive(Subcommand)]
+enum AccountsCommand {
+    /// List the addresses derived from a mnemonic
+    List {
+        /// BIP-39 mnemonic (defaults to anvil's test mnemonic)
+        #[arg(long)]
+        mnemonic: Option<String>,
+        /// Number of accounts to list
+        #[arg(long, default_value_t = accounts::ANVIL_ACCOUNTS)]
+        count: u32,
+    },
+    /// Show the address and private key at one index
+    Derive {
+        /// BIP-39 mnemonic (defaults to anvil's test mnemonic)
+        #[arg(long)]
+        mnemonic: Option<String>,
+        /// Account index in m/44'/60'/0'/0/<index>
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum NodeCommand {
+    /// Start anvil in the background and wait until it answers
+    Start {
+        /// Local profile to start the node for (defaults to the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Stop the node started for this project
+    Stop,
+    /// Show whether the node is running
+    Status,
+}
+
+#[derive(Subcommand)]
+enum ReceiptCommand {
+    /// Show the receipt kind, image ID, journal and seal size
+    Inspect {
+        /// Receipt file, as written by bincode::serialize
+        file: String,
+        /// Solidity type to ABI-decode the journal as, e.g. "(uint256,address)"
+        /// (defaults to the project's `struct Journal`)
+        #[arg(long)]
+        journal_type: Option<String>,
+    },
+    /// Verify the receipt locally against an image ID
+    Verify {
+        /// Receipt file, as written by bincode::serialize
+        file: String,
+        /// Image ID (0x...) or guest name (defaults to the guests of the current build)
+        #[arg(long)]
+        image_id: Option<String>,
+        /// Take guest image IDs from the release build
+        #[arg(long)]
+        release: bool,
+    },
+    /// Print the seal in the encoding the Solidity verifier expects
+    Seal {
+        /// Receipt file, as written by bincode::serialize
+        file: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeystoreCommand {
+    /// Encrypt a private key into ~/.berry/keystores
+    Import {
+        /// Keystore name, used as wallet = "keystore:<name>" in berry.toml
+        name: String,
+    },
+    /// List berry and Foundry keystores
+    List,
+}
+
+#[derive(Subcommand)]
+enum EnvCommand {
+    /// Check that wallet addresses in env files and berry.toml match their keys
+    Check {
+        /// Rewrite mismatched or unchecksummed addresses
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Switch env.sh to another profile
+    Use {
+        /// Profile name from berry.toml
+        profile: String,
+    },
+}
+
+/// Options shared by `berry new` and `berry init`
+#[derive(Args)]
+struct ScaffoldArgs {
+    /// How to handle an enclosing cargo workspace
+    #[arg(long, value_enum, default_value = "ask")]
+    workspace: WorkspaceMode,
+    /// How to bring in the Solidity libraries
+    #[arg(long, value_enum, default_value = "submodule")]
+    lib_mode: LibMode,
+    /// Pin a library to a tag, branch or commit (e.g. forge-std=v1.9.6), except in soldeer mode
+    #[arg(long = "lib-rev", value_name = "LIBRARY=REV")]
+    lib_revs: Vec<String>,
+    /// Pin the Solidity compiler version in foundry.toml
+    #[arg(long)]
+    solc_version: Option<String>,
+    /// Pin the EVM version in foundry.toml
+    #[arg(long)]
+    evm_version: Option<String>,
+}
+
+/// Resolved options for scaffolding a project
+pub struct ScaffoldOptions {
+    pub workspace: WorkspaceMode,
+    pub lib_mode: LibMode,
+    pub revs: Revisions,
+    pub solc_version: Option<String>,
+    pub evm_version: Option<String>,
+}
+
+impl ScaffoldArgs {
+    fn resolve(&self) -> Result<ScaffoldOptions, String> {
+        Ok(ScaffoldOptions {
+            workspace: self.workspace,
+            lib_mode: self.lib_mode,
+            revs: Revisions::resolve(libs::RISC0_ETHEREUM_RELEASE, self.lib_mode, &self.lib_revs)?,
+            solc_version: self.solc_version.clone(),
+            evm_version: self.evm_version.clone(),
+        })
+    }
+}
+
+/// Create a spinner with the standard berry style
+fn new_spinner(message: impl Into<std::borrow::Cow<'static, str>>) -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
+            .template("{spinner:.green} {msg}")
+            .unwrap(),
+    );
+    pb.set_message(message);
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
 /// Get command version output
 fn get_command_version(command: &str, args: &[&str]) -> Option<String> {
     Command::new(command)
@@ -99,7 +445,7 @@ fn check_foundry() -> Result<String, String> {
 
         // Check minimum version requirement
         let version_parts: Vec<&str> = version.split('.').collect();
-        if let (Some(major), Some(minor)) = (version_parts.get(0), version_parts.get(1)) {
+        if let (Some(major), Some(minor)) = (version_parts.first(), version_parts.get(1)) {
             let major = major.parse::<u32>().unwrap_or(0);
             let minor = minor.parse::<u32>().unwrap_or(0);
             if major > 0 || (major == 0 && minor >= 3) {
@@ -125,7 +471,7 @@ fn check_risc0() -> Result<String, String> {
         // Check minimum version requirement
         let version_parts: Vec<&str> = version.split('.').collect();
         if let (Some(major), Some(minor), Some(patch)) = (
-            version_parts.get(0),
+            version_parts.first(),
             version_parts.get(1),
             version_parts.get(2),
         ) {
@@ -164,15 +510,7 @@ fn run_git_command(dir: &str, args: &[&str]) -> Result<(), String> {
 
 /// Set up sparse checkout for the repository
 fn setup_sparse_checkout(dir: &str) -> Result<(), String> {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message("Setting up sparse checkout...");
-    pb.enable_steady_tick(Duration::from_millis(100));
+    let pb = new_spinner("Setting up sparse checkout...");
 
     // Initialize sparse checkout
     run_git_command(dir, &["sparse-checkout", "init", "--cone"])?;
@@ -184,15 +522,7 @@ fn setup_sparse_checkout(dir: &str) -> Result<(), String> {
 
 /// Clone the RISC0 repository
 fn clone_repository(name: &str, _branch: &str) -> Result<(), git2::Error> {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message(format!("Cloning RISC0 repository into {}...", name));
-    pb.enable_steady_tick(Duration::from_millis(100));
+    let pb = new_spinner(format!("Cloning RISC0 repository into {}...", name));
 
     // Clone with specific branch
     Repository::clone_recurse("https://github.com/risc0/risc0-ethereum.git", name)?;
@@ -203,15 +533,7 @@ fn clone_repository(name: &str, _branch: &str) -> Result<(), git2::Error> {
 
 /// Move files from erc20-counter to root and clean up
 fn setup_project_files(dir: &str) -> Result<(), String> {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message("Setting up project files...");
-    pb.enable_steady_tick(Duration::from_millis(100));
+    let pb = new_spinner("Setting up project files...");
 
     let dir_path = PathBuf::from(dir);
     let erc20_path = dir_path.join("examples").join("erc20-counter");
@@ -260,49 +582,79 @@ fn setup_project_files(dir: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Update dependencies in Cargo.toml files
-fn update_cargo_dependencies(dir: &str) -> Result<(), String> {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message("Updating Cargo.toml files...");
-    pb.enable_steady_tick(Duration::from_millis(100));
-
-    let dir_path = PathBuf::from(dir);
-    visit_cargo_files(&dir_path, &pb)?;
-
-    pb.finish_with_message(format!(
-        "{} Cargo.toml files updated successfully",
-        CHECK_MARK
-    ));
-    Ok(())
-}
+/// risc0-ethereum dependency rewrites: (dependency, template line, extra keys of the replacement)
+const CARGO_RULES: &[(&str, &str, &str)] = &[
+    (
+        "risc0-build-ethereum",
+        "risc0-build-ethereum = { path = \"../../build\" }",
+        "",
+    ),
+    (
+        "risc0-ethereum-contracts",
+        "risc0-ethereum-contracts = { path = \"../../contracts\" }",
+        "",
+    ),
+    (
+        "risc0-steel",
+        "risc0-steel = { path = \"../../crates/steel\" }",
+        "",
+    ),
+    (
+        "risc0-steel",
+        "risc0-steel = { path = \"../../../crates/steel\" }",
+        "",
+    ),
+    (
+        "risc0-steel",
+        "risc0-steel = { path = \"../../../../crates/steel\" }",
+        "",
+    ),
+    (
+        "risc0-ethereum-contracts",
+        "risc0-ethereum-contracts = { workspace = true }",
+        "",
+    ),
+    ("risc0-steel", "risc0-steel = { workspace = true }", ""),
+    (
+        "risc0-steel",
+        "risc0-steel = { workspace = true, features = [\"host\"] }",
+        ", features = [\"host\"]",
+    ),
+];
 
-fn visit_cargo_files(dir: &Path, pb: &ProgressBar) -> Result<(), String> {
-    if !dir.is_dir() {
-        return Ok(());
+/// Update dependencies in Cargo.toml files
+fn update_cargo_dependencies(
+    dir: &str,
+    revs: &Revisions,
+    report: &mut Report,
+) -> Result<(), String> {
+    let pb = new_spinner("Updating Cargo.toml files...");
+
+    for &dependency in libs::RISC0_CRATES {
+        report.expect("cargo", dependency);
     }
 
-    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            visit_cargo_files(&path, pb)?;
-        } else if path.file_name().map_or(false, |n| n == "Cargo.toml") {
-            pb.set_message(format!("Updating {}", path.display()));
-            update_cargo_file(&path)?;
-        }
+    let source = revs.cargo_source();
+    for path in walk::project_files(Path::new(dir), "Cargo.toml")? {
+        pb.set_message(format!("Updating {}", path.display()));
+        update_cargo_file(&path, &source, report)?;
     }
 
+    if report.count("cargo") == 0 {
+        pb.finish_with_message(format!(
+            "{} No Cargo.toml dependency rules matched",
+            CROSS_MARK
+        ));
+    } else {
+        pb.finish_with_message(format!(
+            "{} Cargo.toml files updated successfully",
+            CHECK_MARK
+        ));
+    }
     Ok(())
 }
 
-fn update_cargo_file(path: &Path) -> Result<(), String> {
+fn update_cargo_file(path: &Path, source: &str, report: &mut Report) -> Result<(), String> {
     // Read the file content
     let mut content = String::new();
     let mut file =
@@ -312,54 +664,41 @@ fn update_cargo_file(path: &Path) -> Result<(), String> {
 
     // Update dependencies using regex-like replacements
     let mut updated = content;
+    let pinned =
+        |dependency: &str, extra: &str| format!("{} = {{ {}{} }}", dependency, source, extra);
 
     // For methods/Cargo.toml, we need to explicitly set risc0-build-ethereum
     if path.to_string_lossy().contains("methods/Cargo.toml") {
-        updated = updated.replace(
+        updated = report.replace(
+            "cargo",
+            "risc0-build-ethereum",
+            path,
+            &updated,
             "risc0-build-ethereum = { workspace = true }",
-            "risc0-build-ethereum = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
+            &pinned("risc0-build-ethereum", ""),
         );
     } else {
         // For other Cargo.toml files
-        updated = updated
-            .replace(
-                "risc0-build-ethereum = { path = \"../../build\" }",
-                "risc0-build-ethereum = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-            )
-            .replace(
-                "risc0-ethereum-contracts = { path = \"../../contracts\" }",
-                "risc0-ethereum-contracts = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-            )
-            .replace(
-                "risc0-steel = { path = \"../../crates/steel\" }",
-                "risc0-steel = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-            )
-            .replace(
-                "risc0-steel = { path = \"../../../crates/steel\" }",
-                "risc0-steel = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-            )
-            .replace(
-                "risc0-steel = { path = \"../../../../crates/steel\" }",
-                "risc0-steel = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-            )
-            .replace(
-                "risc0-ethereum-contracts = { workspace = true }",
-                "risc0-ethereum-contracts = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-            )
-            .replace(
-                "risc0-steel = { workspace = true }",
-                "risc0-steel = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-            )
-            .replace(
-                "risc0-steel = { workspace = true, features = [\"host\"] }",
-                "risc0-steel = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\", features = [\"host\"] }",
+        for (dependency, from, extra) in CARGO_RULES {
+            updated = report.replace(
+                "cargo",
+                dependency,
+                path,
+                &updated,
+                from,
+                &pinned(dependency, extra),
             );
+        }
 
         // Add features = ["host"] for apps directory
         if path.to_string_lossy().contains("/apps/") {
-            updated = updated.replace(
-                "risc0-steel = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\" }",
-                "risc0-steel = { git = \"https://github.com/risc0/risc0-ethereum\", branch = \"release-1.3\", features = [\"host\"] }",
+            updated = report.replace(
+                "cargo",
+                "risc0-steel host feature",
+                path,
+                &updated,
+                &pinned("risc0-steel", ""),
+                &pinned("risc0-steel", ", features = [\"host\"]"),
             );
         }
     }
@@ -373,248 +712,88 @@ fn update_cargo_file(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
-/// Update foundry.toml configuration
-fn update_foundry_config(dir: &str) -> Result<(), String> {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message("Updating foundry.toml...");
-    pb.enable_steady_tick(Duration::from_millis(100));
-
-    let foundry_path = PathBuf::from(dir).join("foundry.toml");
-    if !foundry_path.exists() {
-        pb.finish_with_message(format!("{} foundry.toml not found", CROSS_MARK));
-        return Ok(());
-    }
-
-    // Read the file content
-    let mut content = String::new();
-    let mut file =
-        fs::File::open(&foundry_path).map_err(|e| format!("Failed to open foundry.toml: {}", e))?;
-    file.read_to_string(&mut content)
-        .map_err(|e| format!("Failed to read foundry.toml: {}", e))?;
-
-    // Update the libs path and add auto_detect_remappings = false
-    let updated = content
-        .replace(
-            "libs = [\"../../lib\", \"../../contracts/src\"]",
-            "libs = [\"lib\"]",
-        )
-        .replace(
-            "[profile.default]",
-            "[profile.default]\nauto_detect_remappings = false",
-        );
-
-    // Write back to file
-    let mut file = fs::File::create(&foundry_path)
-        .map_err(|e| format!("Failed to open foundry.toml for writing: {}", e))?;
-    file.write_all(updated.as_bytes())
-        .map_err(|e| format!("Failed to write to foundry.toml: {}", e))?;
-
-    pb.finish_with_message(format!("{} foundry.toml updated successfully", CHECK_MARK));
-    Ok(())
-}
-
-/// Set up Git submodules
-fn setup_git_submodules(dir: &str) -> Result<(), String> {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message("Setting up Git submodules...");
-    pb.enable_steady_tick(Duration::from_millis(100));
-
-    // Clean up existing lib directory
-    let lib_path = PathBuf::from(dir).join("lib");
-    if lib_path.exists() {
-        fs::remove_dir_all(&lib_path)
-            .map_err(|e| format!("Failed to remove lib directory: {}", e))?;
-    }
-    fs::create_dir_all(&lib_path).map_err(|e| format!("Failed to create lib directory: {}", e))?;
-
-    // Remove existing .git directory to start fresh
-    let git_path = PathBuf::from(dir).join(".git");
-    if git_path.exists() {
-        fs::remove_dir_all(&git_path)
-            .map_err(|e| format!("Failed to remove .git directory: {}", e))?;
-    }
-
-    // Initialize new git repository
-    run_git_command(dir, &["init"])?;
-
-    // Initialize and add submodules
-    run_git_command(dir, &["submodule", "init"])?;
-
-    // Add forge-std
-    pb.set_message("Adding forge-std submodule...");
-    run_git_command(
-        dir,
-        &[
-            "submodule",
-            "add",
-            "https://github.com/foundry-rs/forge-std",
-            "lib/forge-std",
-        ],
-    )?;
-
-    // Add OpenZeppelin contracts
-    pb.set_message("Adding OpenZeppelin contracts submodule...");
-    run_git_command(
-        dir,
-        &[
-            "submodule",
-            "add",
-            "https://github.com/OpenZeppelin/openzeppelin-contracts",
-            "lib/openzeppelin-contracts",
-        ],
-    )?;
-
-    // Add RISC0 ethereum
-    pb.set_message("Adding RISC0 ethereum submodule...");
-    run_git_command(
-        dir,
-        &[
-            "submodule",
-            "add",
-            "-b",
-            "release-1.3",
-            "https://github.com/risc0/risc0-ethereum",
-            "lib/risc0-ethereum",
-        ],
-    )?;
-
-    // Update all submodules recursively
-    pb.set_message("Updating submodules...");
-    run_git_command(
-        dir,
-        &["submodule", "update", "--init", "--recursive", "--quiet"],
-    )?;
-
-    // Reset git state
-    run_git_command(dir, &["reset"])?;
-
-    pb.finish_with_message(format!("{} Git submodules set up successfully", CHECK_MARK));
-    Ok(())
-}
-
-/// Update remappings.txt configuration
-fn update_remappings(dir: &str) -> Result<(), String> {
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message("Updating remappings.txt...");
-    pb.enable_steady_tick(Duration::from_millis(100));
-
-    let remappings_path = PathBuf::from(dir).join("remappings.txt");
-    if !remappings_path.exists() {
-        pb.finish_with_message(format!("{} remappings.txt not found", CROSS_MARK));
-        return Ok(());
-    }
-
-    // Read the file content
-    let mut content = String::new();
-    let mut file = fs::File::open(&remappings_path)
-        .map_err(|e| format!("Failed to open remappings.txt: {}", e))?;
-    file.read_to_string(&mut content)
-        .map_err(|e| format!("Failed to read remappings.txt: {}", e))?;
-
-    // Update the remappings
-    let mut updated = content
-        .replace(
-            "forge-std/=../../lib/forge-std/src/",
-            "forge-std/=lib/forge-std/src/",
-        )
-        .replace(
-            "openzeppelin/=../../lib/openzeppelin-contracts/",
-            "openzeppelin/=lib/openzeppelin-contracts/",
-        )
-        .replace(
-            "risc0/=../../contracts/src/",
-            "risc0/=lib/risc0-ethereum/contracts/src/",
-        );
-
-    // Add OpenZeppelin contracts remapping if not present
-    let oz_remapping = "openzeppelin-contracts/=lib/openzeppelin-contracts/contracts";
-    if !updated.contains(oz_remapping) {
-        if !updated.ends_with('\n') {
-            updated.push('\n');
-        }
-        updated.push_str(oz_remapping);
-        updated.push('\n');
-    }
-
-    // Write back to file
-    let mut file = fs::File::create(&remappings_path)
-        .map_err(|e| format!("Failed to open remappings.txt for writing: {}", e))?;
-    file.write_all(updated.as_bytes())
-        .map_err(|e| format!("Failed to write to remappings.txt: {}", e))?;
-
-    pb.finish_with_message(format!(
-        "{} remappings.txt updated successfully",
-        CHECK_MARK
-    ));
-    Ok(())
-}
-
 /// Initialize a new project
-fn init_project(name: &str) -> Result<(), String> {
+fn init_project(dir: &str, name: &str, options: &ScaffoldOptions) -> Result<(), String> {
     // Check if project directory already exists
-    if Path::new(name).exists() {
+    if Path::new(dir).exists() {
         return Err(format!(
-            "A file or directory named '{}' already exists. Please choose a different name or remove the existing one.",
-            name
+            "A file or directory named '{}' already exists. Please choose a different path or remove the existing one.",
+            dir
         ));
     }
 
     // Clone the repository
-    clone_repository(name, "release-1.3").map_err(|e| e.to_string())?;
+    clone_repository(dir, "release-1.3").map_err(|e| e.to_string())?;
 
     // Switch to the release branch
-    run_git_command(name, &["checkout", "release-1.3"])?;
+    run_git_command(dir, &["checkout", "release-1.3"])?;
 
     // Set up sparse checkout
-    setup_sparse_checkout(name)?;
+    setup_sparse_checkout(dir)?;
 
     // Set up project files
-    setup_project_files(name)?;
+    setup_project_files(dir)?;
+
+    // Record every rewrite for the generation report
+    let mut report = Report::new(dir);
 
     // Update Cargo.toml files
-    update_cargo_dependencies(name)?;
+    update_cargo_dependencies(dir, &options.revs, &mut report)?;
 
     // Update foundry.toml
-    update_foundry_config(name)?;
+    foundry::update_foundry_config(dir, options, &mut report)?;
 
-    // Set up Git submodules
-    setup_git_submodules(name)?;
+    // Install Solidity libraries
+    libs::install_libraries(dir, options.lib_mode, &options.revs, false)?;
 
     // Update remappings.txt
-    update_remappings(name)?;
+    remappings::update_remappings(dir, options.lib_mode, &mut report)?;
+
+    // Join or step out of an enclosing cargo workspace
+    workspace::configure_workspace(Path::new(dir), options.workspace)?;
+
+    // Write the generation report and flag rules that matched nothing
+    report.finish(Path::new(dir))?;
+
+    // Verify the rewrite steps left a consistent project
+    report_project_check(dir);
 
     // Print success message
     println!("\n🫐 Project {} created successfully!", name);
     println!("\nNext steps:");
-    println!("1. berry setup {}", name);
-    println!("2. cd {}", name);
+    println!("1. berry setup {}", dir);
+    println!("2. cd {}", dir);
     println!("3. source env.sh");
-    println!("4. export BONSAI_API_KEY=your_api_key_here  # Get one at https://bonsai.xyz/apply");
-    println!("Run `anvil` in another terminal to start a local Ethereum node");
-    println!("5. ./e2e-test.sh");
+    println!("4. berry e2e  # dev-mode proving on a local node, no Bonsai API key needed");
+    println!(
+        "For real proofs use `berry setup --prover local`, or `--prover bonsai` with BONSAI_API_KEY set"
+    );
     Ok(())
 }
 
+/// Run `berry check` on a freshly generated project, reporting without failing
+fn report_project_check(dir: &str) {
+    match check::check_project(Path::new(dir)) {
+        Ok(problems) if problems.is_empty() => println!("{} Project check passed", CHECK_MARK),
+        Ok(problems) => {
+            println!(
+                "{} Project check found {} problem(s):",
+                CROSS_MARK,
+                problems.len()
+            );
+            check::print_problems(&problems);
+        }
+        Err(e) => println!("{} Project check failed: {}", CROSS_MARK, e),
+    }
+}
+
 /// Set up environment for end-to-end tests
-fn run_setup(dir: Option<&str>) -> Result<(), String> {
+fn run_setup(
+    dir: Option<&str>,
+    profile: Option<&str>,
+    write_key: bool,
+    prover: Option<profile::Prover>,
+) -> Result<(), String> {
     // If directory is provided, change to it first
     if let Some(project_dir) = dir {
         if !Path::new(project_dir).exists() {
@@ -624,175 +803,357 @@ fn run_setup(dir: Option<&str>) -> Result<(), String> {
             .map_err(|e| format!("Failed to change to directory '{}': {}", project_dir, e))?;
     }
 
-    if !Path::new("e2e-test.sh").exists() {
+    if !Path::new("foundry.toml").exists() {
         return Err(
-            "e2e-test.sh not found. Please run this command from your project directory or specify the project directory (e.g., berry setup my-project)"
+            "foundry.toml not found. Please run this command from your project directory or specify the project directory (e.g., berry setup my-project)"
                 .to_string(),
         );
     }
 
-    println!("\nPreparing test environment...");
-    println!("This will:");
-    println!("1. Build the project (cargo build && forge build)");
-    println!("2. Make e2e-test.sh executable");
-    println!("3. Set up environment variables\n");
+    let mut config = profile::Config::load(Path::new("."))?;
+    let name = profile.unwrap_or(config.active_profile()).to_string();
+    if let Some(prover) = prover {
+        config.set_prover(&name, prover)?;
+    }
+    let profile = config.profile(&name)?;
 
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
+    println!(
+        "\nPreparing test environment for profile '{}' with the {} prover...",
+        profile.name, profile.prover
     );
+    println!("This will:");
+    println!("1. Build the guest and host (cargo build)");
+    println!("2. Build the contracts (forge build)");
+    println!("3. Set up environment variables");
+    println!("Build logs go to .berry/logs/\n");
 
-    // Build the project
-    pb.set_message("Building project...");
-    let build_output = Command::new("sh")
-        .arg("-c")
-        .arg("cargo build && forge build")
-        .output()
-        .map_err(|e| format!("Failed to build project: {}", e))?;
-
-    if !build_output.status.success() {
-        return Err(format!(
-            "Build failed: {}",
-            String::from_utf8_lossy(&build_output.stderr)
-        ));
-    }
+    build::run_build_step(Path::new("."), "cargo", Command::new("cargo").arg("build"))?;
+    build::run_build_step(Path::new("."), "forge", Command::new("forge").arg("build"))?;
 
-    // Make test script executable
-    pb.set_message("Making test script executable...");
-    let chmod_output = Command::new("chmod")
-        .arg("+x")
-        .arg("e2e-test.sh")
-        .output()
-        .map_err(|e| format!("Failed to make e2e-test.sh executable: {}", e))?;
+    let pb = new_spinner("Setting up environment variables...");
+    // Set up environment variables for the profile and make it the active one
+    let created_config = !config.exists();
+    environment::write_profile_env(Path::new("."), &profile, write_key)?;
+    environment::activate(Path::new("."), &mut config, &profile)?;
 
-    if !chmod_output.status.success() {
-        return Err(format!(
-            "Failed to make e2e-test.sh executable: {}",
-            String::from_utf8_lossy(&chmod_output.stderr)
-        ));
+    pb.finish_with_message(format!("{} Setup completed successfully", CHECK_MARK));
+    if created_config {
+        println!(
+            "{} Created {} with the local, ci and sepolia profiles",
+            CHECK_MARK,
+            profile::CONFIG_FILE
+        );
     }
 
-    // Set up environment variables
-    pb.set_message("Setting up environment variables...");
-    let env_vars = [
-        ("BONSAI_API_URL", "https://api.bonsai.xyz"),
-        (
-            "ETH_WALLET_ADDRESS",
-            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
-        ),
-        (
-            "ETH_WALLET_PRIVATE_KEY",
-            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
-        ),
-        ("ETH_RPC_URL", "http://localhost:8545"),
-    ];
-
-    // Create env.sh file
-    let mut env_content = String::new();
-    for (key, value) in env_vars {
-        env_content.push_str(&format!("export {}={}\n", key, value));
-    }
-    if env::var("BONSAI_API_KEY").is_err() {
-        env_content.push_str("\n# Get your Bonsai API key from https://bonsai.xyz/apply\n");
-        env_content.push_str("# export BONSAI_API_KEY=your_api_key_here\n");
+    let project_name = dir.unwrap_or(".");
+    println!("\nNext steps:");
+    println!("1. cd {}", project_name);
+    println!(
+        "2. source env.sh  # profile '{}', switch with `berry env use <profile>`",
+        profile.name
+    );
+    match profile.prover {
+        _ if profile.uses_bonsai_mock() => {
+            println!(
+                "3. berry bonsai-mock &  # serves Bonsai at {}",
+                profile.bonsai_api_url.as_deref().unwrap_or_default()
+            );
+            println!("4. berry e2e");
+        }
+        profile::Prover::Bonsai if env::var("BONSAI_API_KEY").is_err() => {
+            println!(
+                "3. export BONSAI_API_KEY=your_api_key_here  # Get one at https://bonsai.xyz/apply"
+            );
+            println!("4. berry e2e");
+        }
+        profile::Prover::Dev => println!("3. berry e2e  # dev mode, receipts are not real proofs"),
+        _ => println!("3. berry e2e"),
     }
 
-    let env_file_path = "env.sh";
-    fs::write(env_file_path, env_content).map_err(|e| format!("Failed to create env.sh: {}", e))?;
+    Ok(())
+}
 
-    // Make env.sh executable
-    Command::new("chmod")
-        .arg("+x")
-        .arg(env_file_path)
-        .output()
-        .map_err(|e| format!("Failed to make env.sh executable: {}", e))?;
+/// Check the required toolchains, printing the status of each
+fn check_dependencies() -> bool {
+    let mut all_deps_ok = true;
 
-    pb.finish_with_message(format!("{} Setup completed successfully", CHECK_MARK));
+    // Check Rust
+    match check_rust() {
+        Ok(version) => println!("{} {}", CHECK_MARK, version),
+        Err(e) => {
+            println!("{} Rust: {}", CROSS_MARK, e);
+            all_deps_ok = false;
+        }
+    }
 
-    let project_name = dir.unwrap_or(".");
-    println!("\nNext steps:");
-    println!("1. cd {}", project_name);
-    println!("2. source env.sh");
-    println!("3. export BONSAI_API_KEY=your_api_key_here  # Get one at https://bonsai.xyz/apply");
-    println!("4. ./e2e-test.sh");
+    // Check Foundry
+    match check_foundry() {
+        Ok(version) => println!("{} {}", CHECK_MARK, version),
+        Err(e) => {
+            println!("{} Foundry: {}", CROSS_MARK, e);
+            all_deps_ok = false;
+        }
+    }
 
-    Ok(())
+    // Check RISC0
+    match check_risc0() {
+        Ok(version) => println!("{} {}", CHECK_MARK, version),
+        Err(e) => {
+            println!("{} RISC0: {}", CROSS_MARK, e);
+            all_deps_ok = false;
+        }
+    }
+
+    all_deps_ok
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match &cli.command {
-        Commands::New { name } => {
-            let mut all_deps_ok = true;
-
-            // Check Rust
-            match check_rust() {
-                Ok(version) => println!("{} {}", CHECK_MARK, version),
+        Commands::New {
+            name,
+            path,
+            scaffold,
+        } => {
+            // Validate the project name as a directory and crate name
+            let name = match validate::validate_project_name(name) {
+                Ok(name) => name,
                 Err(e) => {
-                    println!("{} Rust: {}", CROSS_MARK, e);
-                    all_deps_ok = false;
+                    eprintln!("{} Error: {}", CROSS_MARK, e);
+                    std::process::exit(1);
                 }
-            }
+            };
 
-            // Check Foundry
-            match check_foundry() {
-                Ok(version) => println!("{} {}", CHECK_MARK, version),
-                Err(e) => {
-                    println!("{} Foundry: {}", CROSS_MARK, e);
-                    all_deps_ok = false;
-                }
+            // Validate the project directory
+            let dir = path.clone().unwrap_or_else(|| name.clone());
+            if let Err(e) = validate::validate_project_path(&dir) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
             }
 
-            // Check RISC0
-            match check_risc0() {
-                Ok(version) => println!("{} {}", CHECK_MARK, version),
+            // Resolve the pinned library revisions and other options
+            let options = match scaffold.resolve() {
+                Ok(options) => options,
                 Err(e) => {
-                    println!("{} RISC0: {}", CROSS_MARK, e);
-                    all_deps_ok = false;
+                    eprintln!("{} Error: {}", CROSS_MARK, e);
+                    std::process::exit(1);
                 }
-            }
+            };
 
-            if !all_deps_ok {
+            if !check_dependencies() {
                 return;
             }
 
-            // Validate folder name is not empty
-            if name.trim().is_empty() {
-                eprintln!("{} Error: Folder name cannot be empty", CROSS_MARK);
-                return;
+            // Initialize the project
+            if let Err(e) = init_project(&dir, &name, &options) {
+                eprintln!("{} Error initializing project: {}", CROSS_MARK, e);
+                // Clean up the directory if it was created
+                if Path::new(&dir).exists() {
+                    let _ = fs::remove_dir_all(&dir);
+                }
+                std::process::exit(1);
             }
+        }
+        Commands::Init { subdir, scaffold } => {
+            let options = match scaffold.resolve() {
+                Ok(options) => options,
+                Err(e) => {
+                    eprintln!("{} Error: {}", CROSS_MARK, e);
+                    std::process::exit(1);
+                }
+            };
 
-            // Check if folder already exists
-            if Path::new(&name).exists() {
-                eprintln!(
-                    "{} Error: A file or directory named '{}' already exists",
-                    CROSS_MARK, name
-                );
+            if !check_dependencies() {
                 return;
             }
 
-            // Initialize the project
-            match init_project(name) {
-                Ok(_) => (),
-                Err(e) => {
-                    eprintln!("{} Error initializing project: {}", CROSS_MARK, e);
-                    // Clean up the directory if it was created
-                    if Path::new(&name).exists() {
-                        let _ = fs::remove_dir_all(&name);
-                    }
-                    return;
+            if let Err(e) = init::init_existing(subdir.as_deref(), &options) {
+                eprintln!("{} Error initializing project: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Check { dir } => {
+            if let Err(e) = check::run_check(dir.as_deref()) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Setup {
+            dir,
+            profile,
+            write_key,
+            prover,
+        } => {
+            if let Err(e) = run_setup(dir.as_deref(), profile.as_deref(), *write_key, *prover) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Build {
+            guest,
+            host,
+            contracts,
+            release,
+            dir,
+        } => {
+            let targets = build::BuildTargets {
+                guest: *guest,
+                host: *host,
+                contracts: *contracts,
+                release: *release,
+            };
+            if let Err(e) = build::run_build(dir.as_deref(), &targets) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::ImageId {
+            release,
+            check,
+            dir,
+        } => {
+            if let Err(e) = image_id::run_image_id(dir.as_deref(), *release, *check) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Env {
+            command,
+            shell,
+            profile,
+            dir,
+        } => {
+            let result = match command {
+                Some(EnvCommand::Check { fix }) => environment::check_env(dir.as_deref(), *fix),
+                Some(EnvCommand::Use { profile }) => {
+                    environment::use_profile(dir.as_deref(), profile)
+                }
+                None => environment::print_env(dir.as_deref(), profile.as_deref(), *shell),
+            };
+            if let Err(e) = result {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Accounts { command } => {
+            let result = match command {
+                AccountsCommand::List { mnemonic, count } => {
+                    accounts::list_accounts(mnemonic.as_deref(), *count)
+                }
+                AccountsCommand::Derive { mnemonic, index } => {
+                    accounts::derive_account(mnemonic.as_deref(), *index)
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Node { command, dir } => {
+            let result = match command {
+                NodeCommand::Start { profile } => {
+                    node::start_node(dir.as_deref(), profile.as_deref())
+                }
+                NodeCommand::Stop => node::stop_node(dir.as_deref()),
+                NodeCommand::Status => node::node_status(dir.as_deref()),
+            };
+            if let Err(e) = result {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Exec {
+            input,
+            guest,
+            release,
+            baseline,
+            update_baseline,
+            tolerance,
+            dir,
+        } => {
+            let options = exec::ExecOptions {
+                dir: dir.as_deref(),
+                input,
+                guest: guest.as_deref(),
+                release: *release,
+                baseline: baseline.as_deref(),
+                update_baseline: *update_baseline,
+                tolerance: *tolerance,
+            };
+            if let Err(e) = exec::run_exec(&options) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Receipt { command, dir } => {
+            let result = match command {
+                ReceiptCommand::Inspect { file, journal_type } => {
+                    receipt::inspect(dir.as_deref(), file, journal_type.as_deref())
                 }
+                ReceiptCommand::Verify {
+                    file,
+                    image_id,
+                    release,
+                } => receipt::verify(dir.as_deref(), file, image_id.as_deref(), *release),
+                ReceiptCommand::Seal { file } => receipt::seal(file),
+            };
+            if let Err(e) = result {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Deploy { profile, dir } => {
+            if let Err(e) = deploy::run_deploy(dir.as_deref(), profile.as_deref()) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::E2e {
+            profile,
+            dir,
+            junit,
+            keep_node,
+            account,
+        } => {
+            let options = e2e::E2eOptions {
+                dir: dir.as_deref(),
+                profile: profile.as_deref(),
+                junit: junit.as_deref(),
+                keep_node: *keep_node,
+                account: account.as_deref(),
+            };
+            if let Err(e) = e2e::run_e2e(&options) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        }
+        Commands::BonsaiMock { port, dir } => {
+            if let Err(e) = bonsai_mock::run_bonsai_mock(dir.as_deref(), *port) {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
             }
         }
-        Commands::Setup { dir } => {
-            if let Err(e) = run_setup(dir.as_deref()) {
+        Commands::Keystore { command } => {
+            let result = match command {
+                KeystoreCommand::Import { name } => secrets::import_keystore(name),
+                KeystoreCommand::List => secrets::list_keystores(),
+            };
+            if let Err(e) = result {
                 eprintln!("{} Error: {}", CROSS_MARK, e);
                 std::process::exit(1);
             }
         }
+        Commands::Run {
+            profile,
+            dir,
+            command,
+        } => match environment::run_with_env(dir.as_deref(), profile.as_deref(), command) {
+            Ok(code) => std::process::exit(code),
+            Err(e) => {
+                eprintln!("{} Error: {}", CROSS_MARK, e);
+                std::process::exit(1);
+            }
+        },
     }
 }
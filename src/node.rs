@@ -0,0 +1,389 @@
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+
+use super::{new_spinner, CHECK_MARK, CROSS_MARK};
+use crate::environment::add_to_gitignore;
+use crate::profile::{Config, Profile};
+use crate::rpc;
+
+/// Where the running node is recorded, relative to the project root
+const NODE_STATE: &str = ".berry/node.json";
+
+/// Where anvil's output goes, relative to the project root
+const NODE_LOG: &str = ".berry/logs/anvil.log";
+
+/// How long anvil gets to answer eth_chainId after starting
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many ports `berry node start` tries when another process takes the chosen one
+const START_ATTEMPTS: u32 = 3;
+
+/// How long anvil gets to exit after SIGTERM before it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Nodes started by this process, kept so they are reaped once they exit
+static CHILDREN: Mutex<Vec<Child>> = Mutex::new(Vec::new());
+
+/// A node started by `berry node start`
+#[derive(Serialize, Deserialize)]
+pub struct NodeState {
+    pub pid: u32,
+    pub profile: String,
+    pub rpc_url: String,
+    pub chain_id: u64,
+    pub log: PathBuf,
+}
+
+/// The node started for a project, if it is still running
+pub fn running_node(dir: &Path) -> Option<NodeState> {
+    let content = fs::read_to_string(dir.join(NODE_STATE)).ok()?;
+    let state: NodeState = serde_json::from_str(&content).ok()?;
+    is_node(state.pid).then_some(state)
+}
+
+/// `berry node start`: spawn anvil for a local profile and wait until it answers
+pub fn start_node(dir: Option<&str>, profile: Option<&str>) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let config = Config::load(dir)?;
+    let profile = config.profile(profile.unwrap_or(config.active_profile()))?;
+    if !profile.is_local() {
+        return Err(format!(
+            "Profile '{}' targets chain {}, `berry node` only runs local profiles",
+            profile.name, profile.chain_id
+        ));
+    }
+
+    if let Some(state) = running_node(dir) {
+        println!(
+            "{} anvil is already running at {} (pid {}, profile '{}')",
+            CHECK_MARK, state.rpc_url, state.pid, state.profile
+        );
+        return Ok(());
+    }
+
+    let preferred = profile_port(&profile);
+    let log = dir.join(NODE_LOG);
+    if let Some(parent) = log.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    // Another process may take the port between choosing and binding it
+    let mut port = choose_port(preferred)?;
+    let pb = new_spinner(format!("Starting anvil on port {}...", port));
+    let mut attempt = 1;
+    let child = loop {
+        if let Some(child) = spawn_anvil(&log, &profile, port, &pb)? {
+            break child;
+        }
+        if attempt == START_ATTEMPTS {
+            pb.finish_with_message(format!("{} anvil could not bind a port", CROSS_MARK));
+            return Err(format!(
+                "anvil found its port taken {} times, see {}",
+                START_ATTEMPTS, NODE_LOG
+            ));
+        }
+        attempt += 1;
+        port = free_port()?;
+        pb.set_message(format!("Port taken, starting anvil on port {}...", port));
+    };
+    let rpc_url = node_url(port);
+
+    let state = NodeState {
+        pid: child.id(),
+        profile: profile.name.clone(),
+        rpc_url: rpc_url.clone(),
+        chain_id: profile.chain_id,
+        log: PathBuf::from(NODE_LOG),
+    };
+    let json = serde_json::to_string_pretty(&state)
+        .map_err(|e| format!("Failed to serialize node state: {}", e))?;
+    fs::write(dir.join(NODE_STATE), json + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", NODE_STATE, e))?;
+    add_to_gitignore(dir, "berry runtime state and logs", &[".berry/"])?;
+    if let Ok(mut children) = CHILDREN.lock() {
+        children.push(child);
+    }
+
+    pb.finish_with_message(format!(
+        "{} anvil running at {} (pid {}, chain ID {})",
+        CHECK_MARK, rpc_url, state.pid, state.chain_id
+    ));
+    if port != preferred {
+        println!(
+            "Port from profile '{}' was taken; `berry run` and `berry env` use {} while the node runs",
+            profile.name, rpc_url
+        );
+    }
+    println!("Logs: {}", NODE_LOG);
+    Ok(())
+}
+
+/// Spawn anvil on a port and wait until it listens there and answers with the profile's chain
+///
+/// Returns `None` when anvil could not bind the port because another process holds it.
+fn spawn_anvil(
+    log: &Path,
+    profile: &Profile,
+    port: u16,
+    pb: &ProgressBar,
+) -> Result<Option<Child>, String> {
+    let log_file =
+        fs::File::create(log).map_err(|e| format!("Failed to create {}: {}", NODE_LOG, e))?;
+    let stderr = log_file
+        .try_clone()
+        .map_err(|e| format!("Failed to open {}: {}", NODE_LOG, e))?;
+    let mut command = Command::new("anvil");
+    command
+        .args(["--port", &port.to_string()])
+        .args(["--chain-id", &profile.chain_id.to_string()])
+        .stdin(Stdio::null())
+        .stdout(log_file)
+        .stderr(stderr);
+    if let Ok(mnemonic) = env::var("BERRY_MNEMONIC") {
+        command.args(["--mnemonic", &mnemonic]);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start anvil: {}", e))?;
+
+    // Only probe once our anvil reports the bind, so another node on the port is never taken for it
+    let rpc_url = node_url(port);
+    let started = Instant::now();
+    loop {
+        let output = fs::read_to_string(log).unwrap_or_default();
+        if let Ok(Some(status)) = child.try_wait() {
+            if output.to_lowercase().contains("address already in use") {
+                return Ok(None);
+            }
+            pb.finish_with_message(format!("{} anvil exited with {}", CROSS_MARK, status));
+            return Err(format!("anvil failed to start, see {}", NODE_LOG));
+        }
+        let ready = if listening_on(&output, port) {
+            rpc::chain_id(&rpc_url)
+        } else {
+            Err(format!("anvil did not report listening on port {}", port))
+        };
+        match ready {
+            Ok(chain_id) if chain_id == profile.chain_id => return Ok(Some(child)),
+            Ok(chain_id) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                pb.finish_with_message(format!("{} anvil reported the wrong chain", CROSS_MARK));
+                return Err(format!(
+                    "Node at {} reports chain {}, expected {}",
+                    rpc_url, chain_id, profile.chain_id
+                ));
+            }
+            Err(_) if started.elapsed() < READY_TIMEOUT => {
+                thread::sleep(Duration::from_millis(200))
+            }
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                pb.finish_with_message(format!("{} anvil did not become ready", CROSS_MARK));
+                return Err(format!(
+                    "anvil did not answer within {}s: {}",
+                    READY_TIMEOUT.as_secs(),
+                    e
+                ));
+            }
+        }
+    }
+}
+
+/// Whether anvil's output announces it is listening on a port
+fn listening_on(output: &str, port: u16) -> bool {
+    let suffix = format!(":{}", port);
+    output.lines().any(|line| {
+        let line = line.trim();
+        line.starts_with("Listening on") && line.ends_with(&suffix)
+    })
+}
+
+fn node_url(port: u16) -> String {
+    format!("http://127.0.0.1:{}", port)
+}
+
+/// `berry node stop`: terminate the node started for the project
+pub fn stop_node(dir: Option<&str>) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let Some(state) = running_node(dir) else {
+        let _ = fs::remove_file(dir.join(NODE_STATE));
+        println!("{} No node is running", CHECK_MARK);
+        return Ok(());
+    };
+
+    let pb = new_spinner(format!("Stopping anvil (pid {})...", state.pid));
+    signal(state.pid, "TERM")?;
+    let started = Instant::now();
+    while is_node(state.pid) {
+        if started.elapsed() > STOP_TIMEOUT {
+            signal(state.pid, "KILL")?;
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    fs::remove_file(dir.join(NODE_STATE))
+        .map_err(|e| format!("Failed to remove {}: {}", NODE_STATE, e))?;
+
+    pb.finish_with_message(format!("{} anvil stopped", CHECK_MARK));
+    Ok(())
+}
+
+/// `berry node status`: report whether the node runs and answers
+pub fn node_status(dir: Option<&str>) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let Some(state) = running_node(dir) else {
+        println!(
+            "{} No node is running, start one with `berry node start`",
+            CROSS_MARK
+        );
+        return Ok(());
+    };
+
+    match rpc::call(&state.rpc_url, "eth_blockNumber", serde_json::json!([])) {
+        Ok(block) => println!(
+            "{} anvil running at {} (pid {}, profile '{}', chain ID {}, block {})",
+            CHECK_MARK,
+            state.rpc_url,
+            state.pid,
+            state.profile,
+            state.chain_id,
+            rpc::quantity(&block)?
+        ),
+        Err(e) => println!(
+            "{} anvil (pid {}) is running but not answering at {}: {}",
+            CROSS_MARK, state.pid, state.rpc_url, e
+        ),
+    }
+    println!("Logs: {}", state.log.display());
+    Ok(())
+}
+
+/// The port in the profile's RPC URL, anvil's default if it has none
+fn profile_port(profile: &Profile) -> u16 {
+    profile
+        .rpc_url
+        .rsplit(':')
+        .next()
+        .and_then(|port| port.trim_end_matches('/').parse::<u16>().ok())
+        .unwrap_or(8545)
+}
+
+/// The preferred port if it is free, otherwise any free port
+fn choose_port(preferred: u16) -> Result<u16, String> {
+    if TcpListener::bind(("127.0.0.1", preferred)).is_ok() {
+        return Ok(preferred);
+    }
+    free_port()
+}
+
+/// A port the system reports free right now
+fn free_port() -> Result<u16, String> {
+    TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("Failed to find a free port: {}", e))
+}
+
+/// Whether a pid is still a node, so a reused pid is never signalled
+///
+/// A child of this process keeps its pid until it is reaped, so it is matched by pid;
+/// any other pid must be a live anvil process.
+fn is_node(pid: u32) -> bool {
+    if let Some(exited) = reap(pid) {
+        return !exited;
+    }
+    // Zombies keep their pid until their parent reaps them but are already gone
+    let Ok(output) = Command::new("ps")
+        .args(["-o", "stat=,comm=", "-p", &pid.to_string()])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return false;
+    };
+    let listing = String::from_utf8_lossy(&output.stdout);
+    let Some((stat, command)) = listing.trim().split_once(char::is_whitespace) else {
+        return false;
+    };
+    output.status.success()
+        && !stat.starts_with('Z')
+        && command.trim().rsplit('/').next() == Some("anvil")
+}
+
+/// For a node started by this process, whether it has exited, reaping it if so
+fn reap(pid: u32) -> Option<bool> {
+    let mut children = CHILDREN.lock().ok()?;
+    let index = children.iter().position(|child| child.id() == pid)?;
+    let exited = !matches!(children[index].try_wait(), Ok(None));
+    if exited {
+        children.retain(|child| child.id() != pid);
+    }
+    Some(exited)
+}
+
+fn signal(pid: u32, signal: &str) -> Result<(), String> {
+    let status = Command::new("kill")
+        .args([&format!("-{}", signal), &pid.to_string()])
+        .status()
+        .map_err(|e| format!("Failed to signal pid {}: {}", pid, e))?;
+    if !status.success() && is_node(pid) {
+        return Err(format!("Failed to send SIG{} to pid {}", signal, pid));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reaps_nodes_started_by_this_process() {
+        let child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        CHILDREN.lock().unwrap().push(child);
+        assert!(is_node(pid));
+
+        signal(pid, "TERM").unwrap();
+        let started = Instant::now();
+        while is_node(pid) {
+            assert!(
+                started.elapsed() < STOP_TIMEOUT,
+                "pid {} was not reaped",
+                pid
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(CHILDREN.lock().unwrap().iter().all(|c| c.id() != pid));
+    }
+
+    #[test]
+    fn ignores_other_programs_it_did_not_start() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        assert!(!is_node(child.id()));
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn detects_anvil_listening_on_its_port() {
+        let output = "\n                             _   _\n                            (_) | |\n\nBase Fee\n==================\n\n1000000000\n\nListening on 127.0.0.1:8545\n";
+        assert!(listening_on(output, 8545));
+        assert!(!listening_on(output, 85));
+        assert!(!listening_on(output, 8546));
+        assert!(!listening_on(
+            "Error: Address already in use (os error 98)\n",
+            8545
+        ));
+    }
+}
@@ -0,0 +1,427 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use toml_edit::{value, DocumentMut, Item, Table};
+
+use crate::address::parse_address;
+
+/// Project configuration file holding the network profiles
+pub const CONFIG_FILE: &str = "berry.toml";
+
+/// Profile used when none is given and none has been activated
+pub const DEFAULT_PROFILE: &str = "local";
+
+/// Written to berry.toml when a project has none yet; also the fallback for built-in profiles
+const DEFAULT_CONFIG: &str = r#"# Network profiles for `berry setup --profile <name>` and `berry env use <name>`
+active-profile = "local"
+
+[profile.local]
+rpc-url = "http://localhost:8545"
+chain-id = 31337
+# Wallet source: "anvil" (dev key, local profiles only), "env" (ETH_WALLET_PRIVATE_KEY),
+# "mnemonic:<index>" (BERRY_MNEMONIC, or anvil's test mnemonic on local profiles),
+# "foundry:<account>" (~/.foundry/keystores) or "keystore:<name>" (berry keystore)
+wallet = "anvil"
+# Optional: the address the wallet key must belong to
+# wallet-address = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+# Prover: "dev" (RISC0_DEV_MODE with a mock verifier, local profiles only),
+# "local" (CPU proving) or "bonsai" (needs BONSAI_API_KEY)
+prover = "dev"
+bonsai-api-url = "https://api.bonsai.xyz"
+
+# Distinct mnemonic accounts exported as <ROLE>_ADDRESS and <ROLE>_PRIVATE_KEY
+[profile.local.accounts]
+deployer = 0
+prover = 1
+user = 2
+
+# Offline CI: dev-mode receipts served by `berry bonsai-mock`
+[profile.ci]
+rpc-url = "http://localhost:8545"
+chain-id = 31337
+wallet = "anvil"
+prover = "bonsai"
+bonsai-api-url = "http://127.0.0.1:8081"
+
+[profile.sepolia]
+rpc-url = "https://ethereum-sepolia-rpc.publicnode.com"
+chain-id = 11155111
+wallet = "env"
+prover = "bonsai"
+bonsai-api-url = "https://api.bonsai.xyz"
+# RISC Zero verifier router deployed on Sepolia
+verifier-address = "0x925d8331ddc0a1F0d96E68CF073DFE1d92b69187"
+"#;
+
+/// Chain ID anvil starts with; profiles on it may use the well-known dev keys
+pub const LOCAL_CHAIN_ID: u64 = 31337;
+
+/// Where a profile gets its wallet key from
+#[derive(Clone, PartialEq, Eq)]
+pub enum WalletSource {
+    /// anvil's first default development account
+    Anvil,
+    /// ETH_WALLET_PRIVATE_KEY from the environment running berry
+    Env,
+    /// An account derived from a mnemonic
+    Mnemonic(u32),
+    /// An encrypted Foundry keystore account in ~/.foundry/keystores
+    Foundry(String),
+    /// An encrypted berry keystore in ~/.berry/keystores
+    Keystore(String),
+}
+
+impl FromStr for WalletSource {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source.split_once(':') {
+            None if source == "anvil" => Ok(WalletSource::Anvil),
+            None if source == "env" => Ok(WalletSource::Env),
+            Some(("mnemonic", index)) => index.parse().map(WalletSource::Mnemonic).map_err(|_| {
+                format!(
+                    "Invalid account index '{}' in wallet source '{}'",
+                    index, source
+                )
+            }),
+            Some(("foundry", name)) if !name.is_empty() => {
+                Ok(WalletSource::Foundry(name.to_string()))
+            }
+            Some(("keystore", name)) if !name.is_empty() => {
+                Ok(WalletSource::Keystore(name.to_string()))
+            }
+            _ => Err(format!(
+                "Unknown wallet source '{}', expected \"anvil\", \"env\", \"mnemonic:<index>\", \"foundry:<account>\" or \"keystore:<name>\"",
+                source
+            )),
+        }
+    }
+}
+
+impl fmt::Display for WalletSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletSource::Anvil => write!(f, "anvil"),
+            WalletSource::Env => write!(f, "env"),
+            WalletSource::Mnemonic(index) => write!(f, "mnemonic:{}", index),
+            WalletSource::Foundry(name) => write!(f, "foundry:{}", name),
+            WalletSource::Keystore(name) => write!(f, "keystore:{}", name),
+        }
+    }
+}
+
+/// How the guest is proven
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Prover {
+    /// RISC0_DEV_MODE: fake receipts checked by a mock verifier
+    Dev,
+    /// Real proofs generated on the local CPU
+    Local,
+    /// Real proofs generated remotely by Bonsai
+    Bonsai,
+}
+
+impl FromStr for Prover {
+    type Err = String;
+
+    fn from_str(prover: &str) -> Result<Self, Self::Err> {
+        match prover {
+            "dev" => Ok(Prover::Dev),
+            "local" => Ok(Prover::Local),
+            "bonsai" => Ok(Prover::Bonsai),
+            _ => Err(format!(
+                "Unknown prover '{}', expected \"dev\", \"local\" or \"bonsai\"",
+                prover
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Prover {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Prover::Dev => write!(f, "dev"),
+            Prover::Local => write!(f, "local"),
+            Prover::Bonsai => write!(f, "bonsai"),
+        }
+    }
+}
+
+/// A network the project can be set up against
+pub struct Profile {
+    pub name: String,
+    pub rpc_url: String,
+    pub chain_id: u64,
+    pub wallet: WalletSource,
+    pub wallet_address: Option<String>,
+    pub prover: Prover,
+    pub bonsai_api_url: Option<String>,
+    pub verifier_address: Option<String>,
+    /// Named accounts derived from the profile's mnemonic, as (role, index)
+    pub accounts: Vec<(String, u32)>,
+}
+
+impl Profile {
+    /// Name of the env file generated for this profile
+    pub fn env_file(&self) -> String {
+        format!("env.{}.sh", self.name)
+    }
+
+    /// Whether the profile targets a local development chain
+    pub fn is_local(&self) -> bool {
+        self.chain_id == LOCAL_CHAIN_ID
+    }
+
+    /// Whether Bonsai requests go to `berry bonsai-mock` on this machine
+    pub fn uses_bonsai_mock(&self) -> bool {
+        self.prover == Prover::Bonsai
+            && self.bonsai_api_url.as_deref().is_some_and(|url| {
+                let host = url.split("://").nth(1).unwrap_or(url);
+                host.starts_with("127.0.0.1") || host.starts_with("localhost")
+            })
+    }
+
+    /// Whether receipts are dev-mode fakes that need a mock verifier
+    pub fn fake_receipts(&self) -> bool {
+        self.prover == Prover::Dev || self.uses_bonsai_mock()
+    }
+
+    fn from_table(name: &str, table: &Table) -> Result<Self, String> {
+        let string = |key: &str| -> Result<Option<String>, String> {
+            match table.get(key) {
+                None => Ok(None),
+                Some(item) => item.as_str().map(|s| Some(s.to_string())).ok_or_else(|| {
+                    format!(
+                        "[profile.{}] {} in {} must be a string",
+                        name, key, CONFIG_FILE
+                    )
+                }),
+            }
+        };
+
+        let rpc_url = string("rpc-url")?
+            .ok_or_else(|| format!("[profile.{}] in {} has no rpc-url", name, CONFIG_FILE))?;
+        let chain_id = table
+            .get("chain-id")
+            .and_then(Item::as_integer)
+            .and_then(|id| u64::try_from(id).ok())
+            .ok_or_else(|| {
+                format!(
+                    "[profile.{}] in {} needs a positive integer chain-id",
+                    name, CONFIG_FILE
+                )
+            })?;
+        let wallet = string("wallet")?
+            .map(|w| w.parse())
+            .transpose()
+            .map_err(|e| format!("[profile.{}] in {}: {}", name, CONFIG_FILE, e))?
+            .unwrap_or(if chain_id == LOCAL_CHAIN_ID {
+                WalletSource::Anvil
+            } else {
+                WalletSource::Env
+            });
+
+        let prover = string("prover")?
+            .map(|p| p.parse())
+            .transpose()
+            .map_err(|e| format!("[profile.{}] in {}: {}", name, CONFIG_FILE, e))?
+            .unwrap_or(if chain_id == LOCAL_CHAIN_ID {
+                Prover::Dev
+            } else {
+                Prover::Bonsai
+            });
+
+        let address = |key: &str| -> Result<Option<String>, String> {
+            string(key)?
+                .map(|a| parse_address(&a))
+                .transpose()
+                .map_err(|e| format!("[profile.{}] {} in {}: {}", name, key, CONFIG_FILE, e))
+        };
+
+        let mut accounts = Vec::new();
+        if let Some(roles) = table.get("accounts") {
+            let roles = roles.as_table_like().ok_or_else(|| {
+                format!(
+                    "[profile.{}.accounts] in {} must be a table",
+                    name, CONFIG_FILE
+                )
+            })?;
+            for (role, index) in roles.iter() {
+                let index = index
+                    .as_integer()
+                    .and_then(|i| u32::try_from(i).ok())
+                    .ok_or_else(|| {
+                        format!(
+                            "[profile.{}.accounts] {} in {} must be an account index",
+                            name, role, CONFIG_FILE
+                        )
+                    })?;
+                accounts.push((role.to_string(), index));
+            }
+        }
+
+        let profile = Profile {
+            name: name.to_string(),
+            rpc_url,
+            chain_id,
+            wallet,
+            wallet_address: address("wallet-address")?,
+            prover,
+            bonsai_api_url: string("bonsai-api-url")?,
+            verifier_address: address("verifier-address")?,
+            accounts,
+        };
+        if profile.wallet == WalletSource::Anvil && !profile.is_local() {
+            return Err(format!(
+                "[profile.{}] in {} uses anvil's public dev keys on chain {}; they are only allowed on local profiles (chain-id {})",
+                name, CONFIG_FILE, profile.chain_id, LOCAL_CHAIN_ID
+            ));
+        }
+        if profile.fake_receipts() && !profile.is_local() {
+            return Err(format!(
+                "[profile.{}] in {} gets dev-mode receipts on chain {}; fake receipts only verify against a mock verifier on local profiles (chain-id {})",
+                name, CONFIG_FILE, profile.chain_id, LOCAL_CHAIN_ID
+            ));
+        }
+        Ok(profile)
+    }
+}
+
+/// The project's berry.toml, falling back to the built-in profiles
+pub struct Config {
+    path: PathBuf,
+    doc: DocumentMut,
+    exists: bool,
+}
+
+impl Config {
+    /// Load berry.toml from a project, using the defaults if it does not exist
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(CONFIG_FILE);
+        let exists = path.exists();
+        let content = if exists {
+            fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", CONFIG_FILE, e))?
+        } else {
+            DEFAULT_CONFIG.to_string()
+        };
+        let doc = content
+            .parse::<DocumentMut>()
+            .map_err(|e| format!("Failed to parse {}: {}", CONFIG_FILE, e))?;
+        Ok(Config { path, doc, exists })
+    }
+
+    /// Look up a profile, falling back to the built-in definition of the same name
+    pub fn profile(&self, name: &str) -> Result<Profile, String> {
+        if let Some(table) = profile_table(&self.doc, name) {
+            return Profile::from_table(name, table);
+        }
+        let defaults = default_doc();
+        match profile_table(&defaults, name) {
+            Some(table) => Profile::from_table(name, table),
+            None => Err(format!(
+                "Unknown profile '{}'. Available profiles: {}. Define it under [profile.{}] in {}",
+                name,
+                self.profile_names().join(", "),
+                name,
+                CONFIG_FILE
+            )),
+        }
+    }
+
+    /// Names of the configured and built-in profiles
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for doc in [&self.doc, &default_doc()] {
+            if let Some(profiles) = doc.get("profile").and_then(Item::as_table) {
+                for (name, _) in profiles.iter() {
+                    if !names.iter().any(|n| n == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// The profile `berry env use` last switched to
+    pub fn active_profile(&self) -> &str {
+        self.doc
+            .get("active-profile")
+            .and_then(Item::as_str)
+            .unwrap_or(DEFAULT_PROFILE)
+    }
+
+    pub fn set_active_profile(&mut self, name: &str) {
+        set_value(self.doc.as_table_mut(), "active-profile", name);
+    }
+
+    /// Profiles defined in berry.toml itself
+    pub fn configured_profiles(&self) -> Vec<String> {
+        self.doc
+            .get("profile")
+            .and_then(Item::as_table)
+            .map(|profiles| profiles.iter().map(|(name, _)| name.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Overwrite the wallet-address of a profile defined in berry.toml
+    pub fn set_wallet_address(&mut self, name: &str, address: &str) -> Result<(), String> {
+        let table = self
+            .doc
+            .get_mut("profile")
+            .and_then(|p| p.get_mut(name))
+            .and_then(Item::as_table_mut)
+            .ok_or_else(|| format!("[profile.{}] is not defined in {}", name, CONFIG_FILE))?;
+        set_value(table, "wallet-address", address);
+        Ok(())
+    }
+
+    /// Overwrite the prover of a profile defined in berry.toml
+    pub fn set_prover(&mut self, name: &str, prover: Prover) -> Result<(), String> {
+        let table = self
+            .doc
+            .get_mut("profile")
+            .and_then(|p| p.get_mut(name))
+            .and_then(Item::as_table_mut)
+            .ok_or_else(|| format!("[profile.{}] is not defined in {}", name, CONFIG_FILE))?;
+        set_value(table, "prover", &prover.to_string());
+        Ok(())
+    }
+
+    /// Write berry.toml, creating it from the defaults if needed
+    pub fn save(&mut self) -> Result<(), String> {
+        fs::write(&self.path, self.doc.to_string())
+            .map_err(|e| format!("Failed to write {}: {}", CONFIG_FILE, e))?;
+        self.exists = true;
+        Ok(())
+    }
+
+    /// Whether berry.toml exists on disk
+    pub fn exists(&self) -> bool {
+        self.exists
+    }
+}
+
+/// Set a string value, keeping the comments above an existing key
+fn set_value(table: &mut Table, key: &str, new_value: &str) {
+    match table.get_mut(key) {
+        Some(item) => *item = value(new_value),
+        None => {
+            table.insert(key, value(new_value));
+        }
+    }
+}
+
+fn profile_table<'a>(doc: &'a DocumentMut, name: &str) -> Option<&'a Table> {
+    doc.get("profile")?.get(name)?.as_table()
+}
+
+fn default_doc() -> DocumentMut {
+    DEFAULT_CONFIG
+        .parse()
+        .expect("built-in berry.toml is valid TOML")
+}
@@ -0,0 +1,121 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use risc0_zkvm::{
+    Executor, ExecutorEnv, ExternalProver, FakeReceipt, InnerReceipt, Receipt, SessionInfo,
+};
+
+/// Cycle counts of an executor session
+#[derive(Debug, PartialEq)]
+pub struct ExecStats {
+    pub segments: u64,
+    /// Cycles of every segment padded to its power-of-two size, as they are proven
+    pub total_cycles: u64,
+    pub user_cycles: u64,
+}
+
+impl ExecStats {
+    /// Stats of a session from the `(po2, user cycles)` of each of its segments
+    fn from_segments(segments: impl IntoIterator<Item = (u32, u32)>) -> Self {
+        let mut stats = ExecStats {
+            segments: 0,
+            total_cycles: 0,
+            user_cycles: 0,
+        };
+        for (po2, cycles) in segments {
+            stats.segments += 1;
+            stats.total_cycles += 1 << po2;
+            stats.user_cycles += u64::from(cycles);
+        }
+        stats
+    }
+}
+
+/// Result of running a guest in r0vm
+pub struct Execution {
+    pub stats: ExecStats,
+    pub journal: Vec<u8>,
+    /// What the guest wrote to stdout and stderr
+    pub output: String,
+}
+
+/// Execute a guest ELF in r0vm's executor, feeding it `input` on stdin
+///
+/// With `receipt` set, the bincode fake receipt of the session is written there.
+pub fn execute(elf: &Path, input: &Path, receipt: Option<&Path>) -> Result<Execution, String> {
+    let r0vm = env::var("RISC0_SERVER_PATH").unwrap_or_else(|_| "r0vm".to_string());
+    let binary = fs::read(elf).map_err(|e| format!("Failed to read {}: {}", elf.display(), e))?;
+    let input =
+        fs::read(input).map_err(|e| format!("Failed to read {}: {}", input.display(), e))?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let session = {
+        let env = ExecutorEnv::builder()
+            .write_slice(&input)
+            .stdout(&mut stdout)
+            .stderr(&mut stderr)
+            .build()
+            .map_err(|e| format!("Failed to set up the executor: {}", e))?;
+        ExternalProver::new("r0vm", PathBuf::from(&r0vm))
+            .execute(env, &binary)
+            .map_err(|e| {
+                format!(
+                    "Failed to execute in {}: {} (install it with `rzup install r0vm` or set RISC0_SERVER_PATH)",
+                    r0vm, e
+                )
+            })?
+    };
+
+    if let Some(path) = receipt {
+        write_fake_receipt(path, &session)?;
+    }
+    let mut output = String::from_utf8_lossy(&stdout).into_owned();
+    output.push_str(&String::from_utf8_lossy(&stderr));
+    Ok(Execution {
+        stats: ExecStats::from_segments(session.segments.iter().map(|s| (s.po2, s.cycles))),
+        journal: session.journal.bytes,
+        output,
+    })
+}
+
+/// Write the fake receipt dev mode proves a session with
+fn write_fake_receipt(path: &Path, session: &SessionInfo) -> Result<(), String> {
+    let claim = session
+        .receipt_claim
+        .clone()
+        .ok_or_else(|| "r0vm did not report the session's receipt claim".to_string())?;
+    let receipt = Receipt::new(
+        InnerReceipt::Fake(FakeReceipt::new(claim)),
+        session.journal.bytes.clone(),
+    );
+    let content = bincode::serialize(&receipt)
+        .map_err(|e| format!("Failed to serialize the receipt: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_total_cycles_to_each_segment_size() {
+        let stats = ExecStats::from_segments([(20, 1_000_000), (16, 40_000)]);
+        assert_eq!(
+            stats,
+            ExecStats {
+                segments: 2,
+                total_cycles: (1 << 20) + (1 << 16),
+                user_cycles: 1_040_000,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_session_has_no_cycles() {
+        let stats = ExecStats::from_segments([]);
+        assert_eq!(stats.segments, 0);
+        assert_eq!(stats.total_cycles, 0);
+    }
+}
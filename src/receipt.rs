@@ -0,0 +1,491 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use risc0_zkvm::sha::{Digest, Digestible};
+use risc0_zkvm::{InnerReceipt, Receipt, VerifierContext};
+
+use super::{CHECK_MARK, CROSS_MARK};
+use crate::build::guest_methods;
+use crate::deploy::MOCK_SELECTOR;
+use crate::walk::project_files_matching;
+
+/// Steel library of the installed risc0-ethereum, defining the `Commitment` journals embed
+const STEEL_SOL: &str = "lib/risc0-ethereum/contracts/src/steel/Steel.sol";
+
+/// Steel's `Commitment`, for projects whose risc0-ethereum is not installed yet
+const STEEL_COMMITMENT: &str = "uint256 id; bytes32 digest; bytes32 configID;";
+
+/// Name of the struct the template's guest commits to its journal
+const JOURNAL_STRUCT: &str = "Journal";
+
+/// How deeply structs may nest before the definition is considered circular
+const MAX_STRUCT_DEPTH: usize = 8;
+
+/// A journal field and, for struct types, its own fields
+#[derive(Debug)]
+struct Field {
+    name: String,
+    ty: String,
+    fields: Vec<Field>,
+}
+
+/// `berry receipt inspect`: show what a receipt claims and what its seal costs
+pub fn inspect(dir: Option<&str>, file: &str, journal_type: Option<&str>) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let receipt = read_receipt(file)?;
+    let claim = receipt
+        .claim()
+        .map_err(|e| format!("Failed to read the claim of {}: {}", file, e))?;
+    let claim = claim
+        .as_value()
+        .map_err(|e| format!("{} only carries a pruned claim: {}", file, e))?;
+
+    println!("Kind          {}", kind(&receipt.inner));
+    println!("Image ID      {}", hex_id(&claim.pre.digest()));
+    println!("Exit code     {:?}", claim.exit_code);
+    println!("Seal          {} bytes", receipt.seal_size());
+    match solidity_seal(&receipt) {
+        Ok(seal) => println!(
+            "Solidity seal {} bytes, selector 0x{}",
+            seal.len(),
+            hex::encode(&seal[..4])
+        ),
+        Err(e) => println!("Solidity seal {}", e),
+    }
+
+    let journal = &receipt.journal.bytes;
+    println!(
+        "\nJournal       {} bytes, sha256 {}",
+        journal.len(),
+        hex_id(&receipt.journal.digest())
+    );
+    for chunk in journal.chunks(32) {
+        println!("  {}", hex::encode(chunk));
+    }
+
+    let fields = match journal_type {
+        Some(ty) => Ok(vec![Field {
+            name: "journal".to_string(),
+            ty: ty.to_string(),
+            fields: Vec::new(),
+        }]),
+        None => journal_fields(dir),
+    };
+    println!();
+    match fields.and_then(|fields| decode_journal(&fields, journal)) {
+        Ok(lines) => {
+            println!("{} Journal decoded:", CHECK_MARK);
+            for line in lines {
+                println!("  {}", line);
+            }
+        }
+        Err(e) => println!("{} Journal not decoded: {}", CROSS_MARK, e),
+    }
+    Ok(())
+}
+
+/// `berry receipt verify`: check a receipt's seal and that it proves the expected image
+///
+/// Without `image_id` the receipt must match one of the guests of the current build.
+pub fn verify(
+    dir: Option<&str>,
+    file: &str,
+    image_id: Option<&str>,
+    release: bool,
+) -> Result<(), String> {
+    let dir = Path::new(dir.unwrap_or("."));
+    let receipt = read_receipt(file)?;
+    let candidates: Vec<(Option<String>, String)> = match image_id {
+        Some(id) if id.starts_with("0x") => vec![(None, id.to_string())],
+        Some(name) => {
+            let guest = guest_methods(dir, release)?
+                .into_iter()
+                .find(|g| g.name.eq_ignore_ascii_case(&name.replace('-', "_")))
+                .ok_or_else(|| format!("No guest named {} in the build", name))?;
+            vec![(Some(guest.name), guest.image_id)]
+        }
+        None => guest_methods(dir, release)?
+            .into_iter()
+            .map(|g| (Some(g.name), g.image_id))
+            .collect(),
+    };
+
+    let mut errors = Vec::new();
+    for (name, id) in &candidates {
+        let label = match name {
+            Some(name) => format!("{} ({})", name, id),
+            None => id.clone(),
+        };
+        match receipt.verify(parse_image_id(id)?) {
+            Ok(()) => {
+                println!(
+                    "{} {} receipt verifies for {}",
+                    CHECK_MARK,
+                    kind(&receipt.inner),
+                    label
+                );
+                return Ok(());
+            }
+            Err(e) => errors.push(format!("{}: {}", name.as_deref().unwrap_or(id), e)),
+        }
+    }
+
+    let hint = if matches!(receipt.inner, InnerReceipt::Fake(_))
+        && !VerifierContext::default().dev_mode()
+    {
+        " (fake receipts only verify with RISC0_DEV_MODE=1)"
+    } else {
+        ""
+    };
+    Err(format!(
+        "{} does not verify{}: {}",
+        file,
+        hint,
+        errors.join("; ")
+    ))
+}
+
+/// `berry receipt seal`: print the seal encoding the Solidity verifier router expects
+pub fn seal(file: &str) -> Result<(), String> {
+    let receipt = read_receipt(file)?;
+    println!("0x{}", hex::encode(solidity_seal(&receipt)?));
+    Ok(())
+}
+
+fn read_receipt(file: &str) -> Result<Receipt, String> {
+    let bytes = fs::read(file).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+    bincode::deserialize(&bytes)
+        .map_err(|e| format!("Failed to decode {} as a bincode receipt: {}", file, e))
+}
+
+fn kind(inner: &InnerReceipt) -> String {
+    match inner {
+        InnerReceipt::Composite(composite) => {
+            format!("composite ({} segments)", composite.segments.len())
+        }
+        InnerReceipt::Succinct(_) => "succinct".to_string(),
+        InnerReceipt::Groth16(_) => "groth16".to_string(),
+        InnerReceipt::Fake(_) => "fake (dev mode)".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// Selector followed by the seal, as risc0-ethereum's `encode_seal` produces it
+///
+/// Only Groth16 receipts, and fake receipts for the mock verifier, verify on chain.
+fn solidity_seal(receipt: &Receipt) -> Result<Vec<u8>, String> {
+    let (selector, seal) = match &receipt.inner {
+        InnerReceipt::Groth16(groth16) => (
+            groth16.verifier_parameters.as_bytes()[..4].to_vec(),
+            groth16.seal.clone(),
+        ),
+        InnerReceipt::Fake(fake) => (
+            hex::decode(MOCK_SELECTOR.trim_start_matches("0x"))
+                .map_err(|e| format!("Invalid mock selector: {}", e))?,
+            fake.claim.digest().as_bytes().to_vec(),
+        ),
+        inner => {
+            return Err(format!(
+                "not available for {} receipts, request a Groth16 receipt",
+                kind(inner)
+            ))
+        }
+    };
+    Ok([selector, seal].concat())
+}
+
+/// A digest as the hex of its bytes, the form ImageID.sol and `berry image-id` show
+fn hex_id(digest: &Digest) -> String {
+    format!("0x{}", hex::encode(digest.as_bytes()))
+}
+
+fn parse_image_id(id: &str) -> Result<Digest, String> {
+    let bytes = hex::decode(id.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid image ID {}: {}", id, e))?;
+    Digest::try_from(bytes.as_slice())
+        .map_err(|_| format!("Invalid image ID {}: expected 32 bytes", id))
+}
+
+/// Fields of the project's `struct Journal`, from its Rust `sol!` or Solidity definition
+fn journal_fields(dir: &Path) -> Result<Vec<Field>, String> {
+    let mut structs = BTreeMap::new();
+    let sources =
+        project_files_matching(dir, |name| name.ends_with(".rs") || name.ends_with(".sol"))?;
+    for path in sources.iter().chain([dir.join(STEEL_SOL)].iter()) {
+        if let Ok(content) = fs::read_to_string(path) {
+            for (name, body) in struct_definitions(&content) {
+                structs.entry(name).or_insert(body);
+            }
+        }
+    }
+    structs
+        .entry("Commitment".to_string())
+        .or_insert_with(|| STEEL_COMMITMENT.to_string());
+
+    let body = structs.get(JOURNAL_STRUCT).ok_or_else(|| {
+        format!(
+            "no `struct {}` found in the project, pass --journal-type",
+            JOURNAL_STRUCT
+        )
+    })?;
+    parse_fields(body, &structs, 0)
+}
+
+/// `struct Name { ... }` definitions in Rust `sol!` blocks or Solidity sources
+fn struct_definitions(content: &str) -> Vec<(String, String)> {
+    let mut definitions = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("struct ") {
+        rest = &rest[start + "struct ".len()..];
+        let Some(open) = rest.find('{') else {
+            break;
+        };
+        let name = rest[..open].trim();
+        // Unit structs and the word "struct" in prose have no body of their own
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        let body: Vec<&str> = rest[open + 1..open + close]
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or_default())
+            .collect();
+        let body = body.join("\n");
+        // Plain Rust structs declare `name: Type` fields and are not ABI types
+        if !body.contains(':') {
+            definitions.push((name.to_string(), body));
+        }
+        rest = &rest[open + close..];
+    }
+    definitions
+}
+
+fn parse_fields(
+    body: &str,
+    structs: &BTreeMap<String, String>,
+    depth: usize,
+) -> Result<Vec<Field>, String> {
+    if depth > MAX_STRUCT_DEPTH {
+        return Err("struct definitions nest too deeply".to_string());
+    }
+    let mut fields = Vec::new();
+    for declaration in body.split(';') {
+        let words: Vec<&str> = declaration.split_whitespace().collect();
+        let (ty, name) = match words.as_slice() {
+            [] => continue,
+            [ty, .., name] => (ty, name),
+            _ => {
+                return Err(format!(
+                    "malformed field `{}`, expected a type and a name",
+                    declaration.trim()
+                ))
+            }
+        };
+        // `Steel.Commitment` and `Commitment[]` name the same struct
+        let array = &ty[ty.find('[').unwrap_or(ty.len())..];
+        let base = ty[..ty.len() - array.len()]
+            .rsplit('.')
+            .next()
+            .unwrap_or_default();
+        let field = if DynSolType::parse(base).is_ok() {
+            Field {
+                name: name.to_string(),
+                ty: ty.to_string(),
+                fields: Vec::new(),
+            }
+        } else {
+            let body = structs
+                .get(base)
+                .ok_or_else(|| format!("unknown type {} of field {}", base, name))?;
+            let fields = parse_fields(body, structs, depth + 1)?;
+            let tuple: Vec<&str> = fields.iter().map(|f| f.ty.as_str()).collect();
+            Field {
+                name: name.to_string(),
+                ty: format!("({}){}", tuple.join(","), array),
+                fields: if array.is_empty() { fields } else { Vec::new() },
+            }
+        };
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+/// ABI-decode a journal the way `abi_encode` on the struct encoded it
+fn decode_journal(fields: &[Field], journal: &[u8]) -> Result<Vec<String>, String> {
+    let types: Vec<&str> = fields.iter().map(|f| f.ty.as_str()).collect();
+    let tuple = format!("({})", types.join(","));
+    let ty = DynSolType::parse(&tuple).map_err(|e| format!("invalid type {}: {}", tuple, e))?;
+    let value = ty
+        .abi_decode(journal)
+        .map_err(|e| format!("not a {}: {}", tuple, e))?;
+    let mut lines = Vec::new();
+    if let DynSolValue::Tuple(values) = value {
+        format_fields(fields, &values, 0, &mut lines);
+    }
+    Ok(lines)
+}
+
+fn format_fields(fields: &[Field], values: &[DynSolValue], indent: usize, lines: &mut Vec<String>) {
+    for (field, value) in fields.iter().zip(values) {
+        let pad = "  ".repeat(indent);
+        match value {
+            DynSolValue::Tuple(inner) if !field.fields.is_empty() => {
+                lines.push(format!("{}{}:", pad, field.name));
+                format_fields(&field.fields, inner, indent + 1, lines);
+            }
+            value => lines.push(format!("{}{}: {}", pad, field.name, format_value(value))),
+        }
+    }
+}
+
+fn format_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(i, _) => i.to_string(),
+        DynSolValue::Uint(u, _) => u.to_string(),
+        DynSolValue::FixedBytes(word, size) => format!("0x{}", hex::encode(&word[..*size])),
+        DynSolValue::Address(address) => address.to_checksum(None),
+        DynSolValue::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        DynSolValue::String(s) => format!("{:?}", s),
+        DynSolValue::Array(items) | DynSolValue::FixedArray(items) | DynSolValue::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(format_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        value => format!("{:?}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_dyn_abi::DynSolValue;
+    use risc0_zkvm::{FakeReceipt, Groth16Receipt, Groth16ReceiptVerifierParameters, ReceiptClaim};
+
+    use super::*;
+
+    const IMAGE_ID: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn definitions(source: &str) -> BTreeMap<String, String> {
+        let mut structs: BTreeMap<String, String> =
+            struct_definitions(source).into_iter().collect();
+        structs
+            .entry("Commitment".to_string())
+            .or_insert_with(|| STEEL_COMMITMENT.to_string());
+        structs
+    }
+
+    #[test]
+    fn finds_sol_structs_but_not_rust_structs() {
+        let source = r#"
+            sol! {
+                /// The struct committed to the journal
+                struct Journal {
+                    Steel.Commitment commitment; // the block
+                    address tokenContract;
+                }
+            }
+            struct Args { rpc_url: String }
+            struct Unit;
+        "#;
+        let definitions = struct_definitions(source);
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].0, "Journal");
+        let words: Vec<&str> = definitions[0].1.split_whitespace().collect();
+        assert_eq!(
+            words,
+            [
+                "Steel.Commitment",
+                "commitment;",
+                "address",
+                "tokenContract;"
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_nested_steel_commitment() {
+        let structs =
+            definitions("struct Journal { Steel.Commitment commitment; address tokenContract; }");
+        let fields = parse_fields(&structs[JOURNAL_STRUCT], &structs, 0).unwrap();
+        assert_eq!(fields[0].ty, "(uint256,bytes32,bytes32)");
+        assert_eq!(fields[1].ty, "address");
+
+        let journal = DynSolValue::Tuple(vec![
+            DynSolValue::Tuple(vec![
+                DynSolValue::Uint(7u64.try_into().unwrap(), 256),
+                DynSolValue::FixedBytes([0x11; 32].into(), 32),
+                DynSolValue::FixedBytes([0x22; 32].into(), 32),
+            ]),
+            DynSolValue::Address(
+                "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+                    .parse()
+                    .unwrap(),
+            ),
+        ])
+        .abi_encode_params();
+        let lines = decode_journal(&fields, &journal).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "commitment:".to_string(),
+                "  id: 7".to_string(),
+                format!("  digest: 0x{}", "11".repeat(32)),
+                format!("  configID: 0x{}", "22".repeat(32)),
+                "tokenContract: 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_struct_definitions() {
+        let structs = definitions("struct Journal { uint256; address account; }");
+        assert!(parse_fields(&structs[JOURNAL_STRUCT], &structs, 0)
+            .unwrap_err()
+            .contains("malformed field `uint256`"));
+
+        let structs = definitions("struct Journal { Missing value; }");
+        assert!(parse_fields(&structs[JOURNAL_STRUCT], &structs, 0)
+            .unwrap_err()
+            .contains("unknown type Missing"));
+
+        let structs = definitions("struct Journal { Journal inner; }");
+        assert!(parse_fields(&structs[JOURNAL_STRUCT], &structs, 0).is_err());
+    }
+
+    #[test]
+    fn encodes_fake_seal_for_the_mock_verifier() {
+        let claim = ReceiptClaim::ok(IMAGE_ID, vec![1, 2, 3]);
+        let receipt = Receipt::new(
+            InnerReceipt::Fake(FakeReceipt::new(claim.clone())),
+            vec![1, 2, 3],
+        );
+        let seal = solidity_seal(&receipt).unwrap();
+        assert_eq!(seal[..4], [0xff; 4]);
+        assert_eq!(seal[4..], *claim.digest().as_bytes());
+    }
+
+    #[test]
+    fn encodes_groth16_seal_with_the_verifier_selector() {
+        // encode_seal prefixes the first four bytes of the verifier parameters digest
+        let parameters = Groth16ReceiptVerifierParameters::default().digest();
+        assert_eq!(
+            hex_id(&parameters),
+            "0xbb001d444841d70e8bc0c7d034b349044bf3cf0117afb702b2f1e898b7dd13cc"
+        );
+        let claim = ReceiptClaim::ok(IMAGE_ID, Vec::new());
+        let receipt = Receipt::new(
+            InnerReceipt::Groth16(Groth16Receipt::new(
+                vec![0x5a; 256],
+                claim.into(),
+                parameters,
+            )),
+            Vec::new(),
+        );
+        let seal = solidity_seal(&receipt).unwrap();
+        assert_eq!(hex::encode(&seal[..4]), "bb001d44");
+        assert_eq!(seal[4..], [0x5a; 256]);
+    }
+}
@@ -0,0 +1,363 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{new_spinner, CHECK_MARK, CROSS_MARK};
+use crate::libs::{LibMode, Library, LIBRARIES};
+use crate::report::{find_line, Report};
+
+/// Where the template lives inside the risc0-ethereum repository
+const TEMPLATE_PATH: &[&str] = &["examples", "erc20-counter"];
+
+/// A Solidity import remapping: `[context:]prefix=target`
+#[derive(Clone, PartialEq, Eq)]
+pub struct Remapping {
+    pub context: Option<String>,
+    pub prefix: String,
+    pub target: String,
+}
+
+impl FromStr for Remapping {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (lhs, target) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid remapping '{}', expected prefix=target", line))?;
+        let (context, prefix) = match lhs.split_once(':') {
+            Some((context, prefix)) => (Some(context.trim().to_string()), prefix),
+            None => (None, lhs),
+        };
+        let prefix = prefix.trim();
+        let target = target.trim();
+        if prefix.is_empty() || target.is_empty() {
+            return Err(format!(
+                "Invalid remapping '{}', prefix and target must not be empty",
+                line
+            ));
+        }
+
+        Ok(Remapping {
+            context: context.filter(|c| !c.is_empty()),
+            prefix: prefix.to_string(),
+            target: target.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Remapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(context) = &self.context {
+            write!(f, "{}:", context)?;
+        }
+        write!(f, "{}={}", self.prefix, self.target)
+    }
+}
+
+impl Remapping {
+    fn new(prefix: &str, target: &str) -> Self {
+        Remapping {
+            context: None,
+            prefix: prefix.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    fn key(&self) -> (Option<&str>, &str) {
+        (self.context.as_deref(), &self.prefix)
+    }
+}
+
+/// An ordered set of remappings
+#[derive(Default)]
+pub struct Remappings(pub Vec<Remapping>);
+
+impl Remappings {
+    /// Parse remappings.txt content, ignoring blank lines and comments
+    pub fn parse(content: &str) -> Result<Self, String> {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Remappings)
+    }
+
+    /// Add a remapping unless one with the same context and prefix exists
+    pub fn add(&mut self, remapping: Remapping) -> bool {
+        if self.0.iter().any(|r| r.key() == remapping.key()) {
+            return false;
+        }
+        self.0.push(remapping);
+        true
+    }
+
+    /// Drop later duplicates of the same context and prefix, then sort
+    pub fn normalize(&mut self) {
+        let mut unique = Remappings::default();
+        for remapping in self.0.drain(..) {
+            unique.add(remapping);
+        }
+        self.0 = unique.0;
+        self.0.sort_by(|a, b| a.key().cmp(&b.key()));
+    }
+
+    /// Render as remappings.txt content
+    pub fn render(&self) -> String {
+        self.0.iter().map(|r| format!("{}\n", r)).collect()
+    }
+}
+
+/// Update remappings.txt configuration
+pub fn update_remappings(dir: &str, mode: LibMode, report: &mut Report) -> Result<(), String> {
+    let pb = new_spinner("Updating remappings.txt...");
+
+    let remappings_path = Path::new(dir).join("remappings.txt");
+    if !remappings_path.exists() {
+        pb.finish_with_message(format!("{} remappings.txt not found", CROSS_MARK));
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&remappings_path)
+        .map_err(|e| format!("Failed to read remappings.txt: {}", e))?;
+    let mut remappings = Remappings::parse(&content)?;
+
+    // Point every path that escapes the project onto the lib/ layout
+    for remapping in &mut remappings.0 {
+        let before = remapping.to_string();
+        remapping.target = rebase_escaping_path(&remapping.target, mode)?;
+        if let Some(context) = &remapping.context {
+            remapping.context = Some(rebase_escaping_path(context, mode)?);
+        }
+        let after = remapping.to_string();
+        if after != before {
+            let line = find_line(&content, &[&remapping.prefix, "=.."]);
+            report.record(
+                "remappings",
+                "rebase",
+                &remappings_path,
+                line,
+                before,
+                after,
+            );
+        }
+    }
+
+    // Fill in remappings for the libraries actually installed
+    for remapping in derive_remappings(Path::new(dir), mode)? {
+        let rendered = remapping.to_string();
+        if remappings.add(remapping) {
+            report.record("remappings", "derive", &remappings_path, None, "", rendered);
+        }
+    }
+    remappings.normalize();
+
+    fs::write(&remappings_path, remappings.render())
+        .map_err(|e| format!("Failed to write to remappings.txt: {}", e))?;
+
+    let missing = missing_targets(Path::new(dir), &remappings);
+    if missing.is_empty() {
+        pb.finish_with_message(format!(
+            "{} remappings.txt updated successfully",
+            CHECK_MARK
+        ));
+    } else {
+        for remapping in &missing {
+            pb.println(format!(
+                "  {} {} points to a directory that does not exist",
+                CROSS_MARK, remapping
+            ));
+        }
+        pb.finish_with_message(format!(
+            "{} remappings.txt updated with {} missing target(s)",
+            CROSS_MARK,
+            missing.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Map a template path that climbs out of the project onto the installed libraries
+///
+/// The template lives in `examples/erc20-counter` of risc0-ethereum, so
+/// `../../lib/<name>` refers to a library next to risc0-ethereum and any other
+/// `../../<path>` to a path inside risc0-ethereum itself.
+pub fn rebase_escaping_path(path: &str, mode: LibMode) -> Result<String, String> {
+    let mut parts: Vec<&str> = path.split('/').collect();
+    let ups = parts.iter().take_while(|p| **p == "..").count();
+    if ups == 0 {
+        return Ok(path.to_string());
+    }
+    if ups > TEMPLATE_PATH.len() {
+        return Err(format!(
+            "Path '{}' escapes the risc0-ethereum repository and cannot be mapped into lib/",
+            path
+        ));
+    }
+
+    let mut repo_path: Vec<&str> = TEMPLATE_PATH[..TEMPLATE_PATH.len() - ups].to_vec();
+    repo_path.extend(parts.drain(ups..));
+
+    if let ["lib", name, rest @ ..] = repo_path.as_slice() {
+        if let Some(lib) = LIBRARIES.iter().find(|lib| lib.name == *name) {
+            return Ok(join(&lib.root(mode), rest));
+        }
+    }
+    Ok(join(&Library::get("risc0-ethereum").root(mode), &repo_path))
+}
+
+/// Default remappings for the libraries present in the project
+fn derive_remappings(dir: &Path, mode: LibMode) -> Result<Vec<Remapping>, String> {
+    let mut derived = Vec::new();
+
+    for lib in LIBRARIES {
+        let root = lib.root(mode);
+        if !dir.join(&root).is_dir() {
+            continue;
+        }
+        match lib.name {
+            "forge-std" => derived.push(Remapping::new("forge-std/", &format!("{}/src/", root))),
+            "openzeppelin-contracts" => {
+                derived.push(Remapping::new("openzeppelin/", &format!("{}/", root)));
+                derived.push(Remapping::new(
+                    "openzeppelin-contracts/",
+                    &format!("{}/contracts/", root),
+                ));
+            }
+            "risc0-ethereum" => derived.push(Remapping::new(
+                "risc0/",
+                &format!("{}/contracts/src/", root),
+            )),
+            _ => {}
+        }
+    }
+
+    // Any other library follows forge's convention of remapping to src/ if present
+    let libs_dir = match mode {
+        LibMode::Soldeer => "dependencies",
+        _ => "lib",
+    };
+    if let Ok(entries) = fs::read_dir(dir.join(libs_dir)) {
+        let mut others = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let root = format!("{}/{}", libs_dir, name);
+            let known = LIBRARIES.iter().any(|lib| lib.root(mode) == root);
+            if known || name.starts_with('.') || !entry.path().is_dir() {
+                continue;
+            }
+            let target = if entry.path().join("src").is_dir() {
+                format!("{}/src/", root)
+            } else {
+                format!("{}/", root)
+            };
+            others.push(Remapping::new(&format!("{}/", name), &target));
+        }
+        others.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+        derived.extend(others);
+    }
+
+    Ok(derived)
+}
+
+/// Remappings whose target directory does not exist
+pub fn missing_targets<'a>(dir: &Path, remappings: &'a Remappings) -> Vec<&'a Remapping> {
+    remappings
+        .0
+        .iter()
+        .filter(|r| !dir.join(r.target.trim_end_matches('/')).is_dir())
+        .collect()
+}
+
+fn join(root: &str, rest: &[&str]) -> String {
+    let mut path = root.to_string();
+    for part in rest {
+        path.push('/');
+        path.push_str(part);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_contexts_and_skips_comments() {
+        let remappings = Remappings::parse(
+            "# comment\n\nforge-std/=lib/forge-std/src/\n// another\nsrc/:openzeppelin/ = lib/openzeppelin-contracts/\n",
+        )
+        .unwrap();
+        assert_eq!(remappings.0.len(), 2);
+        assert_eq!(remappings.0[0].context, None);
+        assert_eq!(remappings.0[0].prefix, "forge-std/");
+        assert_eq!(remappings.0[0].target, "lib/forge-std/src/");
+        assert_eq!(remappings.0[1].context.as_deref(), Some("src/"));
+        assert_eq!(remappings.0[1].prefix, "openzeppelin/");
+        assert_eq!(remappings.0[1].target, "lib/openzeppelin-contracts/");
+        assert_eq!(
+            remappings.render(),
+            "forge-std/=lib/forge-std/src/\nsrc/:openzeppelin/=lib/openzeppelin-contracts/\n"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_remappings() {
+        assert!(Remappings::parse("forge-std/").is_err());
+        assert!(Remappings::parse("=lib/forge-std/src/").is_err());
+        assert!(Remappings::parse("forge-std/=").is_err());
+    }
+
+    #[test]
+    fn normalize_keeps_the_first_of_each_prefix() {
+        let mut remappings = Remappings::parse(
+            "risc0/=lib/risc0-ethereum/contracts/src/\nforge-std/=a/\nforge-std/=b/\n",
+        )
+        .unwrap();
+        remappings.normalize();
+        assert_eq!(
+            remappings.render(),
+            "forge-std/=a/\nrisc0/=lib/risc0-ethereum/contracts/src/\n"
+        );
+    }
+
+    #[test]
+    fn rebases_template_paths_onto_installed_libraries() {
+        let rebase = |path| rebase_escaping_path(path, LibMode::Submodule).unwrap();
+        assert_eq!(rebase("lib/forge-std/src/"), "lib/forge-std/src/");
+        assert_eq!(rebase("../../lib/forge-std/src/"), "lib/forge-std/src/");
+        assert_eq!(
+            rebase("../../lib/openzeppelin-contracts/contracts/"),
+            "lib/openzeppelin-contracts/contracts/"
+        );
+        assert_eq!(
+            rebase("../../contracts/src/"),
+            "lib/risc0-ethereum/contracts/src/"
+        );
+        assert_eq!(
+            rebase("../erc20/contracts/"),
+            "lib/risc0-ethereum/examples/erc20/contracts/"
+        );
+    }
+
+    #[test]
+    fn rebases_onto_soldeer_dependencies() {
+        let rebase = |path| rebase_escaping_path(path, LibMode::Soldeer).unwrap();
+        assert_eq!(
+            rebase("../../lib/forge-std/src/"),
+            "dependencies/forge-std-1.9.4/src/"
+        );
+        assert_eq!(
+            rebase("../../contracts/src/"),
+            "dependencies/risc0-ethereum-1.3/contracts/src/"
+        );
+    }
+
+    #[test]
+    fn rejects_paths_outside_risc0_ethereum() {
+        assert!(rebase_escaping_path("../../../forge-std/src/", LibMode::Submodule).is_err());
+    }
+}
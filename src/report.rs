@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::CROSS_MARK;
+
+/// Where the generation report is written, relative to the project root
+pub const REPORT_PATH: &str = ".berry/generation-report.json";
+
+/// A single rewrite applied to a project file
+#[derive(Serialize)]
+pub struct Transformation {
+    pub step: &'static str,
+    pub rule: String,
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub before: String,
+    pub after: String,
+}
+
+/// Everything the rewrite steps changed while generating a project
+#[derive(Default, Serialize)]
+pub struct Report {
+    pub transformations: Vec<Transformation>,
+    pub warnings: Vec<String>,
+    #[serde(skip)]
+    expected: Vec<(&'static str, String)>,
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+impl Report {
+    /// Start a report for the project rooted at `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Report {
+            root: root.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Resolve files recorded from now on against a new root
+    pub fn set_root(&mut self, root: impl Into<PathBuf>) {
+        self.root = root.into();
+    }
+
+    /// Record a rewrite; `file` may be absolute or relative to the project root
+    pub fn record(
+        &mut self,
+        step: &'static str,
+        rule: impl Into<String>,
+        file: &Path,
+        line: Option<usize>,
+        before: impl Into<String>,
+        after: impl Into<String>,
+    ) {
+        let file = file.strip_prefix(&self.root).unwrap_or(file).to_path_buf();
+        self.transformations.push(Transformation {
+            step,
+            rule: rule.into(),
+            file,
+            line,
+            before: before.into(),
+            after: after.into(),
+        });
+    }
+
+    /// Declare a rule that must fire at least once for the step to be considered successful
+    pub fn expect(&mut self, step: &'static str, rule: impl Into<String>) {
+        self.expected.push((step, rule.into()));
+    }
+
+    /// Number of rewrites recorded for a step
+    pub fn count(&self, step: &str) -> usize {
+        self.transformations
+            .iter()
+            .filter(|t| t.step == step)
+            .count()
+    }
+
+    /// Replace every occurrence of `from`, recording the line of each match
+    pub fn replace(
+        &mut self,
+        step: &'static str,
+        rule: &str,
+        file: &Path,
+        content: &str,
+        from: &str,
+        to: &str,
+    ) -> String {
+        for (index, _) in content.match_indices(from) {
+            let line = content[..index].matches('\n').count() + 1;
+            self.record(step, rule, file, Some(line), from, to);
+        }
+        content.replace(from, to)
+    }
+
+    /// Warn about expected rules that never fired, then write the report
+    pub fn finish(mut self, dir: &Path) -> Result<(), String> {
+        let unmatched: Vec<String> = self
+            .expected
+            .iter()
+            .filter(|(step, rule)| {
+                !self
+                    .transformations
+                    .iter()
+                    .any(|t| t.step == *step && t.rule == *rule)
+            })
+            .map(|(step, rule)| {
+                format!(
+                    "{}: expected rule `{}` matched nothing; the template may have changed",
+                    step, rule
+                )
+            })
+            .collect();
+        for warning in &unmatched {
+            println!("{} Warning: {}", CROSS_MARK, warning);
+        }
+        self.warnings.extend(unmatched);
+
+        let path = dir.join(REPORT_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&self)
+            .map_err(|e| format!("Failed to serialize generation report: {}", e))?;
+        fs::write(&path, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        println!(
+            "Generation report with {} change(s) written to {}",
+            self.transformations.len(),
+            REPORT_PATH
+        );
+        Ok(())
+    }
+}
+
+/// Find the first line containing all of the given fragments
+pub fn find_line(content: &str, fragments: &[&str]) -> Option<usize> {
+    content
+        .lines()
+        .position(|line| fragments.iter().all(|f| line.contains(f)))
+        .map(|index| index + 1)
+}
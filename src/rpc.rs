@@ -0,0 +1,115 @@
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+/// How long a single JSON-RPC request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A failed JSON-RPC request
+#[derive(Debug)]
+pub struct RpcError {
+    pub message: String,
+    /// The node could not be reached or was briefly overloaded, so retrying may succeed
+    pub transient: bool,
+}
+
+/// Send a JSON-RPC request and return its result
+pub fn call(url: &str, method: &str, params: Value) -> Result<Value, String> {
+    request(url, method, params).map_err(|e| e.message)
+}
+
+/// Send a JSON-RPC request, telling transient failures apart from real ones
+pub fn request(url: &str, method: &str, params: Value) -> Result<Value, RpcError> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .build()
+        .into();
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let mut response = agent.post(url).send_json(&request).map_err(|e| RpcError {
+        message: format!("{} request to {} failed: {}", method, url, e),
+        transient: is_transient(&e),
+    })?;
+    let failed = |message: String| RpcError {
+        message,
+        transient: false,
+    };
+    let mut body: Value = response
+        .body_mut()
+        .read_json()
+        .map_err(|e| failed(format!("Invalid {} response from {}: {}", method, url, e)))?;
+
+    if let Some(error) = body.get("error") {
+        return Err(failed(format!("{} failed: {}", method, error)));
+    }
+    body.get_mut("result")
+        .map(Value::take)
+        .ok_or_else(|| failed(format!("{} response from {} has no result", method, url)))
+}
+
+/// Whether a request failed to reach the node or was turned away while it is busy
+fn is_transient(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::StatusCode(status) => matches!(status, 429 | 502 | 503 | 504),
+        ureq::Error::Timeout(_) | ureq::Error::ConnectionFailed => true,
+        ureq::Error::Io(e) => matches!(
+            e.kind(),
+            ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::TimedOut
+        ),
+        _ => false,
+    }
+}
+
+/// Parse a hex quantity such as `0x7a69`
+pub fn quantity(value: &Value) -> Result<u64, String> {
+    let hex_value = value
+        .as_str()
+        .ok_or_else(|| format!("Expected a hex quantity, got {}", value))?;
+    u64::from_str_radix(hex_value.trim_start_matches("0x"), 16)
+        .map_err(|_| format!("Invalid hex quantity '{}'", hex_value))
+}
+
+/// The chain ID reported by a node
+pub fn chain_id(url: &str) -> Result<u64, String> {
+    quantity(&call(url, "eth_chainId", json!([]))?)
+}
+
+/// The 4-byte selector of a function signature such as `get()`
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn classifies_overloaded_nodes_as_transient() {
+        assert!(is_transient(&ureq::Error::StatusCode(429)));
+        assert!(is_transient(&ureq::Error::StatusCode(503)));
+        assert!(!is_transient(&ureq::Error::StatusCode(400)));
+        assert!(!is_transient(&ureq::Error::StatusCode(404)));
+    }
+
+    #[test]
+    fn unreachable_node_is_transient() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .port();
+        let error = request(
+            &format!("http://127.0.0.1:{}", port),
+            "eth_chainId",
+            json!([]),
+        )
+        .unwrap_err();
+        assert!(error.transient, "{}", error.message);
+    }
+}
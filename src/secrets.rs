@@ -0,0 +1,199 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use super::CHECK_MARK;
+use crate::accounts::{derive, profile_mnemonic};
+use crate::address::{address_of, check_pair};
+use crate::profile::{Profile, WalletSource};
+
+/// anvil's first default development account
+pub const ANVIL_ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+pub const ANVIL_PRIVATE_KEY: &str =
+    "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// Environment variable checked for a keystore password before prompting
+const PASSWORD_VAR: &str = "BERRY_KEYSTORE_PASSWORD";
+
+/// A wallet resolved from a profile's secret source
+pub struct Wallet {
+    pub address: String,
+    pub private_key: String,
+}
+
+/// Resolve the wallet key of a profile, decrypting keystores as needed
+///
+/// The address is always derived from the key; a configured address that does
+/// not match it is an error.
+pub fn resolve_wallet(profile: &Profile) -> Result<Wallet, String> {
+    let (private_key, given_address) = match &profile.wallet {
+        WalletSource::Anvil => (ANVIL_PRIVATE_KEY.to_string(), None),
+        WalletSource::Env => {
+            let private_key = env::var("ETH_WALLET_PRIVATE_KEY").map_err(|_| {
+                format!(
+                    "Profile '{}' reads its wallet from ETH_WALLET_PRIVATE_KEY, which is not set",
+                    profile.name
+                )
+            })?;
+            (private_key, env::var("ETH_WALLET_ADDRESS").ok())
+        }
+        WalletSource::Mnemonic(index) => {
+            let account = derive(&profile_mnemonic(profile)?, *index)?;
+            (account.private_key, None)
+        }
+        WalletSource::Foundry(account) => {
+            let path = home_dir()?.join(".foundry/keystores").join(account);
+            let label = format!("Foundry account '{}'", account);
+            (decrypt(&path, &label)?, None)
+        }
+        WalletSource::Keystore(name) => {
+            let path = keystore_dir()?.join(name);
+            (decrypt(&path, &format!("berry keystore '{}'", name))?, None)
+        }
+    };
+
+    let mut address = address_of(&private_key)?;
+    for given in given_address.iter().chain(&profile.wallet_address) {
+        address = check_pair(given, &private_key)
+            .map_err(|e| format!("Profile '{}': {}", profile.name, e))?;
+    }
+    Ok(Wallet {
+        address,
+        private_key,
+    })
+}
+
+/// The named accounts of a profile, derived from its mnemonic
+pub fn role_accounts(profile: &Profile) -> Result<Vec<(String, Wallet)>, String> {
+    if profile.accounts.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mnemonic = profile_mnemonic(profile)?;
+    profile
+        .accounts
+        .iter()
+        .map(|(role, index)| {
+            let account = derive(&mnemonic, *index)?;
+            let wallet = Wallet {
+                address: account.address,
+                private_key: account.private_key,
+            };
+            Ok((role.to_uppercase().replace('-', "_"), wallet))
+        })
+        .collect()
+}
+
+/// `berry keystore import <name>`: encrypt a private key into the berry keystore
+pub fn import_keystore(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid keystore name '{}'", name));
+    }
+    let dir = keystore_dir()?;
+    if dir.join(name).exists() {
+        return Err(format!(
+            "Keystore '{}' already exists in {}",
+            name,
+            dir.display()
+        ));
+    }
+
+    let private_key = read_secret("Private key: ")?;
+    let key = parse_private_key(&private_key)?;
+    let password = match env::var(PASSWORD_VAR) {
+        Ok(password) => password,
+        Err(_) => {
+            let password = read_secret("New keystore password: ")?;
+            if read_secret("Repeat password: ")? != password {
+                return Err("Passwords do not match".to_string());
+            }
+            password
+        }
+    };
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    eth_keystore::encrypt_key(&dir, &mut rand::thread_rng(), key, password, Some(name))
+        .map_err(|e| format!("Failed to write keystore '{}': {}", name, e))?;
+
+    println!(
+        "{} Saved keystore '{}' to {}",
+        CHECK_MARK,
+        name,
+        dir.join(name).display()
+    );
+    println!("Use it with wallet = \"keystore:{}\" in berry.toml", name);
+    Ok(())
+}
+
+/// `berry keystore list`: show the keystores available to profiles
+pub fn list_keystores() -> Result<(), String> {
+    for (prefix, dir) in [
+        ("keystore", keystore_dir()?),
+        ("foundry", home_dir()?.join(".foundry/keystores")),
+    ] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter(|e| e.path().is_file())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        for name in names {
+            println!("{}:{}", prefix, name);
+        }
+    }
+    Ok(())
+}
+
+/// Decode a hex private key, with or without 0x
+pub fn parse_private_key(private_key: &str) -> Result<Vec<u8>, String> {
+    let hex_key = private_key.trim();
+    let hex_key = hex_key.strip_prefix("0x").unwrap_or(hex_key);
+    let key = hex::decode(hex_key).map_err(|_| "Private key is not valid hex".to_string())?;
+    if key.len() != 32 {
+        return Err(format!(
+            "Private key must be 32 bytes, got {} bytes",
+            key.len()
+        ));
+    }
+    Ok(key)
+}
+
+fn decrypt(path: &Path, label: &str) -> Result<String, String> {
+    if !path.is_file() {
+        return Err(format!("{} not found at {}", label, path.display()));
+    }
+    let password = match env::var(PASSWORD_VAR) {
+        Ok(password) => password,
+        Err(_) => read_secret(&format!("Password for {}: ", label))?,
+    };
+    let key = eth_keystore::decrypt_key(path, password)
+        .map_err(|e| format!("Failed to decrypt {}: {}", label, e))?;
+    Ok(format!("0x{}", hex::encode(key)))
+}
+
+/// Read a secret from the terminal without echoing, or a line from piped stdin
+fn read_secret(prompt: &str) -> Result<String, String> {
+    if io::stdin().is_terminal() {
+        return rpassword::prompt_password(prompt)
+            .map_err(|e| format!("Failed to read from terminal: {}", e));
+    }
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read from stdin: {}", e))?;
+    Ok(line.trim().to_string())
+}
+
+fn keystore_dir() -> Result<PathBuf, String> {
+    Ok(home_dir()?.join(".berry/keystores"))
+}
+
+fn home_dir() -> Result<PathBuf, String> {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| "HOME is not set, cannot locate keystores".to_string())
+}
@@ -0,0 +1,215 @@
+use std::path::{Component, Path};
+
+/// Rust keywords, which cargo refuses as package names
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Package names that clash with the standard library or cargo build artifacts
+const CARGO_RESERVED: &[&str] = &[
+    "alloc",
+    "build",
+    "core",
+    "deps",
+    "examples",
+    "incremental",
+    "proc-macro",
+    "proc_macro",
+    "std",
+    "test",
+];
+
+/// Names that are unsafe as a directory on at least one supported platform
+const RESERVED_DIR_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Validate a project name, checking the Cargo package name derived from it;
+/// returns the trimmed name
+pub fn validate_project_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Project name cannot be empty".to_string());
+    }
+
+    check_path_component(name)?;
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        let suggestion = suggest_name(name);
+        return Err(format!(
+            "Project name '{}' may only contain ASCII letters, digits, '-' and '_'. Try '{}' instead.",
+            name, suggestion
+        ));
+    }
+
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "Project name '{}' must start with a letter, as Cargo packages cannot start with a digit or separator. Try 'app-{}' instead.",
+            name,
+            name.trim_start_matches(['-', '_'])
+        ));
+    }
+
+    let package = cargo_package_name(name);
+    if RUST_KEYWORDS.contains(&package.as_str()) || CARGO_RESERVED.contains(&package.as_str()) {
+        return Err(format!(
+            "Project name '{}' is reserved by Rust or Cargo. Try '{}-app' instead.",
+            name, package
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
+/// Validate the directory a project will be created in
+pub fn validate_project_path(path: &str) -> Result<(), String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("Project path cannot be empty".to_string());
+    }
+    if trimmed != path {
+        return Err(format!(
+            "Project path '{}' has leading or trailing whitespace. Remove it or quote the path correctly.",
+            path
+        ));
+    }
+    if Path::new(path).exists() {
+        return Err(format!(
+            "A file or directory named '{}' already exists. Please choose a different path or remove the existing one.",
+            path
+        ));
+    }
+    Ok(())
+}
+
+/// Validate a subdirectory to scaffold into, which must stay inside the current directory
+pub fn validate_subdir(subdir: &str) -> Result<(), String> {
+    if subdir.trim().is_empty() {
+        return Err("Subdirectory cannot be empty".to_string());
+    }
+    let escapes = Path::new(subdir)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(format!(
+            "Subdirectory '{}' must be a relative path inside the current directory, without '..'.",
+            subdir
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a name is a single, normal relative path component
+fn check_path_component(name: &str) -> Result<(), String> {
+    let mut components = Path::new(name).components();
+    let is_single_normal =
+        matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none();
+
+    if !is_single_normal || name.contains(['/', '\\']) {
+        return Err(format!(
+            "Project name '{}' must be a plain directory name, not a path. Use `berry new <name> --path {}` to choose where the project is created.",
+            name, name
+        ));
+    }
+    if name.starts_with('.') {
+        return Err(format!(
+            "Project name '{}' cannot start with '.', as that creates a hidden directory.",
+            name
+        ));
+    }
+    if RESERVED_DIR_NAMES.contains(&name.to_ascii_lowercase().as_str()) {
+        return Err(format!(
+            "Project name '{}' is a reserved device name on Windows. Try '{}-app' instead.",
+            name, name
+        ));
+    }
+    Ok(())
+}
+
+/// Derive a Cargo package name (lowercase, dash separated)
+fn cargo_package_name(name: &str) -> String {
+    name.to_ascii_lowercase().replace('_', "-")
+}
+
+/// Suggest a name with unsupported characters replaced by dashes
+fn suggest_name(name: &str) -> String {
+    let mut suggestion = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            suggestion.push(c.to_ascii_lowercase());
+        } else if !suggestion.ends_with('-') {
+            suggestion.push('-');
+        }
+    }
+    let suggestion = suggestion.trim_matches('-').to_string();
+    if suggestion.is_empty() {
+        "my-project".to_string()
+    } else {
+        suggestion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_and_trims_valid_names() {
+        assert_eq!(validate_project_name(" my_app ").unwrap(), "my_app");
+        assert_eq!(
+            validate_project_name("erc20-counter").unwrap(),
+            "erc20-counter"
+        );
+    }
+
+    #[test]
+    fn rejects_names_that_are_not_plain_directories() {
+        assert!(validate_project_name("").is_err());
+        assert!(validate_project_name("apps/counter").is_err());
+        assert!(validate_project_name("..").is_err());
+        assert!(validate_project_name(".hidden").is_err());
+        assert!(validate_project_name("COM1").is_err());
+    }
+
+    #[test]
+    fn suggests_a_name_for_unsupported_characters() {
+        let error = validate_project_name("My Cool App!").unwrap_err();
+        assert!(error.contains("Try 'my-cool-app' instead"), "{}", error);
+        assert!(validate_project_name("1app").is_err());
+        assert!(validate_project_name("_app").is_err());
+    }
+
+    #[test]
+    fn rejects_reserved_package_names() {
+        let error = validate_project_name("Crate").unwrap_err();
+        assert!(error.contains("reserved by Rust or Cargo"), "{}", error);
+        assert!(validate_project_name("std").is_err());
+        assert!(validate_project_name("proc_macro").is_err());
+    }
+
+    #[test]
+    fn validates_project_paths() {
+        assert!(validate_project_path("").is_err());
+        assert!(validate_project_path(" new-project").is_err());
+        assert!(validate_project_path("src").is_err());
+        assert!(validate_project_path("target/berry-validate-does-not-exist").is_ok());
+    }
+
+    #[test]
+    fn keeps_subdirectories_inside_the_current_directory() {
+        assert!(validate_subdir("apps/counter").is_ok());
+        assert!(validate_subdir("./counter").is_ok());
+        assert!(validate_subdir(" ").is_err());
+        assert!(validate_subdir("../counter").is_err());
+        assert!(validate_subdir("apps/../../counter").is_err());
+        assert!(validate_subdir("/tmp/counter").is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+
+/// Directories that never hold project files berry should touch
+pub const EXCLUDED_DIRS: &[&str] = &[
+    "lib",
+    "target",
+    "node_modules",
+    "dependencies",
+    "out",
+    "cache",
+];
+
+/// Every file with the given name in the project, in a stable order
+///
+/// Hidden directories, excluded directories and anything matched by a
+/// `.gitignore` inside the project are skipped, whether or not the project is a
+/// git repository yet. Ignore files above the project root are not consulted.
+pub fn project_files(root: &Path, name: &str) -> Result<Vec<PathBuf>, String> {
+    project_files_matching(root, |file_name| file_name == name)
+}
+
+/// Every project file whose name passes `matches`, skipping the same directories
+/// as [`project_files`]
+pub fn project_files_matching(
+    root: &Path,
+    matches: impl Fn(&str) -> bool,
+) -> Result<Vec<PathBuf>, String> {
+    let walker = WalkBuilder::new(root)
+        .hidden(true)
+        .parents(false)
+        .git_global(false)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            entry.depth() == 0 || !is_dir || !EXCLUDED_DIRS.iter().any(|d| entry.file_name() == *d)
+        })
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("Failed to walk {}: {}", root.display(), e))?;
+        if entry.file_type().is_some_and(|t| t.is_file())
+            && entry.file_name().to_str().is_some_and(&matches)
+        {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
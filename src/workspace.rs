@@ -0,0 +1,381 @@
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Component, Path, PathBuf};
+
+use clap::ValueEnum;
+use toml_edit::{value, Array, DocumentMut, Item, Table, TableLike};
+
+use super::{CHECK_MARK, CROSS_MARK};
+use crate::walk::project_files;
+
+/// How to handle a cargo workspace enclosing the generated project
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum WorkspaceMode {
+    /// Ask before joining an enclosing workspace
+    #[default]
+    Ask,
+    /// Register the project crates as members of the enclosing workspace
+    Adopt,
+    /// Keep the project as an independent workspace
+    Standalone,
+}
+
+/// Join or exclude the project from an enclosing cargo workspace, if there is one
+pub fn configure_workspace(project_dir: &Path, mode: WorkspaceMode) -> Result<(), String> {
+    let Some(root_manifest) = find_enclosing_workspace(project_dir) else {
+        return Ok(());
+    };
+
+    let adopt = match mode {
+        WorkspaceMode::Adopt => true,
+        WorkspaceMode::Standalone => false,
+        WorkspaceMode::Ask => confirm(&format!(
+            "Found an enclosing cargo workspace at {}. Register the project crates as its members?",
+            root_manifest.display()
+        )),
+    };
+
+    if adopt {
+        adopt_workspace(&root_manifest, project_dir)
+    } else {
+        exclude_from_workspace(&root_manifest, project_dir)
+    }
+}
+
+/// Find the nearest `Cargo.toml` above the project that declares a `[workspace]`
+pub fn find_enclosing_workspace(project_dir: &Path) -> Option<PathBuf> {
+    let project_dir = project_dir.canonicalize().ok()?;
+    project_dir
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| read_manifest(manifest).is_ok_and(|doc| doc.get("workspace").is_some()))
+}
+
+/// Register the host and methods crates in the enclosing workspace, exclude the guest
+/// crates and drop the nested workspace
+fn adopt_workspace(root_manifest: &Path, project_dir: &Path) -> Result<(), String> {
+    let project_manifest = project_dir.join("Cargo.toml");
+    let project_doc = read_manifest(&project_manifest)?;
+    let mut root_doc = read_manifest(root_manifest)?;
+    let rel = relative_dir(root_manifest, project_dir)?;
+
+    let project_ws = project_doc
+        .get("workspace")
+        .and_then(Item::as_table)
+        .cloned()
+        .ok_or_else(|| format!("{} has no [workspace] table", project_manifest.display()))?;
+
+    // Guest crates keep their own workspace: risc0-build compiles them for riscv32im
+    // separately, so they are excluded rather than adopted
+    let guests = find_nested_workspaces(project_dir)?;
+    let mut members = Vec::new();
+    if let Some(listed) = project_ws.get("members").and_then(Item::as_array) {
+        for member in listed.iter().filter_map(|m| m.as_str()) {
+            members.extend(
+                expand_member(project_dir, member)?
+                    .into_iter()
+                    .filter(|m| !guests.contains(m)),
+            );
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let root_ws = root_doc["workspace"]
+        .as_table_mut()
+        .ok_or_else(|| format!("[workspace] in {} is not a table", root_manifest.display()))?;
+
+    // Register members
+    let root_members = root_ws
+        .entry("members")
+        .or_insert_with(|| value(Array::new()))
+        .as_array_mut()
+        .ok_or("workspace.members is not an array")?;
+    for member in &members {
+        let member = join_rel(&rel, member);
+        if !root_members.iter().any(|m| m.as_str() == Some(&member)) {
+            root_members.push(member);
+        }
+    }
+    let root_exclude = root_ws
+        .entry("exclude")
+        .or_insert_with(|| value(Array::new()))
+        .as_array_mut()
+        .ok_or("workspace.exclude is not an array")?;
+    for guest in &guests {
+        let guest = join_rel(&rel, guest);
+        if !root_exclude.iter().any(|e| e.as_str() == Some(&guest)) {
+            root_exclude.push(guest);
+        }
+    }
+
+    // Hoist shared dependencies, rebasing path dependencies onto the root
+    if let Some(deps) = project_ws.get("dependencies").and_then(Item::as_table_like) {
+        let root_deps = table_entry(root_ws, "dependencies")?;
+        for (name, item) in deps.iter() {
+            let mut item = item.clone();
+            rebase_path(&mut item, &rel);
+            match root_deps.get(name) {
+                None => {
+                    root_deps.insert(name, item);
+                }
+                Some(existing) if !same_item(existing, &item) => warnings.push(format!(
+                    "workspace dependency `{}` differs from the enclosing workspace; keeping {}",
+                    name,
+                    existing.to_string().trim()
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+
+    // Hoist package defaults, inlining any that clash with the root's
+    let mut inlined = Vec::new();
+    if let Some(package) = project_ws.get("package").and_then(Item::as_table_like) {
+        let root_package = table_entry(root_ws, "package")?;
+        for (key, item) in package.iter() {
+            match root_package.get(key) {
+                None => {
+                    root_package.insert(key, item.clone());
+                }
+                Some(existing) if !same_item(existing, item) => {
+                    inlined.push((key.to_string(), item.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    for member in &members {
+        let manifest = project_dir.join(member).join("Cargo.toml");
+        if !inlined.is_empty() && manifest.exists() {
+            let mut doc = read_manifest(&manifest)?;
+            if let Some(package) = doc.get_mut("package").and_then(Item::as_table_like_mut) {
+                for (key, item) in &inlined {
+                    if package.contains_key(key) {
+                        package.insert(key, item.clone());
+                    }
+                }
+            }
+            write_manifest(&manifest, &doc)?;
+        }
+    }
+
+    // Patches are only honoured in the workspace root
+    if let Some(patches) = project_doc.get("patch").and_then(Item::as_table_like) {
+        let root_patches = table_entry(root_doc.as_table_mut(), "patch")?;
+        for (source, entries) in patches.iter() {
+            let Some(entries) = entries.as_table_like() else {
+                continue;
+            };
+            let root_entries = table_entry(root_patches, source)?;
+            for (name, item) in entries.iter() {
+                let mut item = item.clone();
+                rebase_path(&mut item, &rel);
+                if !root_entries.contains_key(name) {
+                    root_entries.insert(name, item);
+                }
+            }
+        }
+    }
+
+    if project_doc.get("profile").is_some() {
+        warnings.push(format!(
+            "[profile] settings in {} are ignored inside a workspace; move them to {} if needed",
+            project_manifest.display(),
+            root_manifest.display()
+        ));
+    }
+
+    write_manifest(root_manifest, &root_doc)?;
+
+    // Remove the nested workspace, keeping any package it declares
+    if project_doc.get("package").is_some() {
+        let mut doc = project_doc;
+        doc.remove("workspace");
+        doc.remove("patch");
+        write_manifest(&project_manifest, &doc)?;
+    } else {
+        fs::remove_file(&project_manifest)
+            .map_err(|e| format!("Failed to remove {}: {}", project_manifest.display(), e))?;
+    }
+    let lock = project_dir.join("Cargo.lock");
+    if lock.exists() {
+        fs::remove_file(&lock)
+            .map_err(|e| format!("Failed to remove {}: {}", lock.display(), e))?;
+    }
+
+    for warning in &warnings {
+        println!("{} Warning: {}", CROSS_MARK, warning);
+    }
+    println!(
+        "{} Registered {} crate(s) in the workspace at {} and excluded {} guest crate(s)",
+        CHECK_MARK,
+        members.len(),
+        root_manifest.display(),
+        guests.len()
+    );
+    Ok(())
+}
+
+/// Add the project to the enclosing workspace's `exclude` list
+fn exclude_from_workspace(root_manifest: &Path, project_dir: &Path) -> Result<(), String> {
+    let mut root_doc = read_manifest(root_manifest)?;
+    let rel = relative_dir(root_manifest, project_dir)?;
+
+    let exclude = root_doc["workspace"]
+        .as_table_mut()
+        .ok_or_else(|| format!("[workspace] in {} is not a table", root_manifest.display()))?
+        .entry("exclude")
+        .or_insert_with(|| value(Array::new()))
+        .as_array_mut()
+        .ok_or("workspace.exclude is not an array")?;
+    if !exclude.iter().any(|e| e.as_str() == Some(&rel)) {
+        exclude.push(rel.as_str());
+        write_manifest(root_manifest, &root_doc)?;
+    }
+
+    println!(
+        "{} Excluded {} from the workspace at {}",
+        CHECK_MARK,
+        rel,
+        root_manifest.display()
+    );
+    Ok(())
+}
+
+/// Expand a workspace member, supporting a trailing `/*` glob
+fn expand_member(project_dir: &Path, member: &str) -> Result<Vec<String>, String> {
+    let Some(prefix) = member.strip_suffix("/*") else {
+        return Ok(vec![member.to_string()]);
+    };
+
+    let mut members = Vec::new();
+    let dir = project_dir.join(prefix);
+    for entry in
+        fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+    {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if entry.path().join("Cargo.toml").exists() {
+            members.push(format!(
+                "{}/{}",
+                prefix,
+                entry.file_name().to_string_lossy()
+            ));
+        }
+    }
+    members.sort();
+    Ok(members)
+}
+
+/// Find crates below the project root that declare their own `[workspace]`
+fn find_nested_workspaces(root: &Path) -> Result<Vec<String>, String> {
+    let mut nested = Vec::new();
+    for manifest in project_files(root, "Cargo.toml")? {
+        let Some(dir) = manifest.parent().and_then(|d| d.strip_prefix(root).ok()) else {
+            continue;
+        };
+        if dir.as_os_str().is_empty() {
+            continue;
+        }
+        if read_manifest(&manifest).is_ok_and(|doc| doc.get("workspace").is_some()) {
+            nested.push(dir.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(nested)
+}
+
+/// Prefix a dependency's `path` with the project's location in the workspace
+fn rebase_path(item: &mut Item, rel: &str) {
+    if let Some(path) = item.get_mut("path") {
+        if let Some(current) = path.as_str() {
+            let rebased = join_rel(rel, current);
+            *path = value(rebased);
+            if let Some(value) = path.as_value_mut() {
+                value.decor_mut().set_prefix(" ");
+            }
+        }
+    }
+}
+
+/// Get or create a sub-table
+fn table_entry<'a>(
+    table: &'a mut dyn TableLike,
+    key: &str,
+) -> Result<&'a mut dyn TableLike, String> {
+    table
+        .entry(key)
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_like_mut()
+        .ok_or_else(|| format!("`{}` is not a table", key))
+}
+
+/// Compare two items ignoring formatting
+fn same_item(a: &Item, b: &Item) -> bool {
+    let normalize = |item: &Item| item.to_string().split_whitespace().collect::<String>();
+    normalize(a) == normalize(b)
+}
+
+/// Path of the project directory relative to the workspace root, with `/` separators
+fn relative_dir(root_manifest: &Path, project_dir: &Path) -> Result<String, String> {
+    let root = root_manifest
+        .parent()
+        .and_then(|p| p.canonicalize().ok())
+        .ok_or("Failed to resolve the workspace root")?;
+    let project = project_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", project_dir.display(), e))?;
+    let rel = project
+        .strip_prefix(&root)
+        .map_err(|_| "Project is not inside the enclosing workspace".to_string())?;
+    Ok(rel
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Join a project-relative path onto the project's location, normalising `.`
+fn join_rel(rel: &str, path: &str) -> String {
+    let path = path.trim_start_matches("./");
+    match (rel.is_empty(), path.is_empty() || path == ".") {
+        (true, _) => path.to_string(),
+        (false, true) => rel.to_string(),
+        (false, false) => format!("{}/{}", rel, path),
+    }
+}
+
+/// Ask a yes/no question, defaulting to yes; non-interactive sessions answer no
+fn confirm(question: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [Y/n] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "" | "y" | "yes"
+    )
+}
+
+pub(crate) fn read_manifest(path: &Path) -> Result<DocumentMut, String> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+pub(crate) fn write_manifest(path: &Path, doc: &DocumentMut) -> Result<(), String> {
+    fs::write(path, doc.to_string())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}